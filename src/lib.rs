@@ -1,4 +1,4 @@
-#![cfg_attr(not(test), no_std)]
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
 #![deny(missing_docs)]
 
 //! A `no_std`, no allocation, `core::fmt::Write`able buffer.
@@ -16,23 +16,593 @@
 //!     assert_eq!(buffer.as_str(), "12");
 //! }
 //! ```
+//!
+//! ## Features
+//!
+//! - `serde`: implements `Serialize` and `Deserialize`, the buffer is
+//!   (de)serialized as a string.
+//! - `defmt`: implements `defmt::Format`, the buffer is logged as a string.
+//! - `ufmt`: implements `ufmt::uWrite`, so the buffer can be written with
+//!   `uwrite!`.
+//! - `embedded-io`: implements `embedded_io::Write`, to use the buffer as a
+//!   byte sink. Bytes written this way are not required to be valid UTF-8.
+//! - `zeroize`: implements `zeroize::Zeroize`, wiping the whole backing array.
+//!   Wrap the buffer in `zeroize::Zeroizing` to wipe it when it is dropped.
+//! - `std`: implements `std::io::Write`, with the same caveat about UTF-8,
+//!   and `std::error::Error` for the error types.
+//! - `heapless`: adds conversions from and to `heapless::String`.
+//! - `panic-on-overflow`: `core::fmt::Write` writes that don't fit panic,
+//!   reporting the requested and available bytes, instead of returning an
+//!   error. Only affects the [`OverflowPolicy::Fail`] policy.
+//! - `debug-raw`: adds `WriteBuffer::debug_raw` exposing the whole backing
+//!   array, for diagnostics only.
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+use core::convert::TryFrom;
+use core::ffi::CStr;
 use core::fmt::{self, Display, Formatter};
+use core::ops::{AddAssign, Deref, DerefMut, Range};
+
+/// Writes formatted data into a [`WriteBuffer`], like `write!`, returning a
+/// [`CapacityError`] on overflow.
+///
+/// ```
+/// use e_write_buffer::{ewrite, CapacityError, WriteBuffer};
+///
+/// let mut buffer: WriteBuffer<4> = WriteBuffer::new();
+/// ewrite!(buffer, "{}", 12).unwrap();
+/// assert_eq!(
+///     ewrite!(buffer, "{}", 345),
+///     Err(CapacityError {
+///         requested: 3,
+///         available: 2,
+///     })
+/// );
+/// ```
+#[macro_export]
+macro_rules! ewrite {
+    ($dst:expr, $($arg:tt)*) => {
+        $dst.write_fmt_checked(::core::format_args!($($arg)*))
+    };
+}
+
+/// Creates a [`WriteBuffer`] containing the concatenation of the given `&str`
+/// constants, with a capacity of exactly their total length.
+///
+/// The buffer is built at compile time, so the content always fits.
+///
+/// ```
+/// use e_write_buffer::{ewrite_buffer, WriteBuffer};
+///
+/// const NAME: &str = "sensor";
+/// let buffer: WriteBuffer<10> = ewrite_buffer!(NAME, "-", "01", "\n");
+/// assert_eq!(buffer.as_str(), "sensor-01\n");
+/// ```
+#[macro_export]
+macro_rules! ewrite_buffer {
+    ($($s:expr),* $(,)?) => {{
+        // Items are not hygienic, these names must not shadow the caller's
+        const __E_WRITE_BUFFER_PARTS: &[&str] = &[$($s),*];
+        const __E_WRITE_BUFFER_LEN: usize = 0 $(+ $s.len())*;
+        const __E_WRITE_BUFFER: $crate::WriteBuffer<__E_WRITE_BUFFER_LEN> =
+            $crate::WriteBuffer::__from_parts(__E_WRITE_BUFFER_PARTS);
+        __E_WRITE_BUFFER
+    }};
+}
+
+/// The error returned when a write does not fit in the remaining space of a
+/// buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CapacityError {
+    /// How many bytes the write needed.
+    pub requested: usize,
+    /// How many bytes were left in the buffer.
+    pub available: usize,
+}
+
+impl Display for CapacityError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "buffer capacity exceeded: requested {} bytes, {} available",
+            self.requested, self.available
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CapacityError {}
+
+/// The error returned when a byte index does not fall on a UTF-8 character
+/// boundary of the written content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CharBoundaryError {
+    /// The offending byte index.
+    pub index: usize,
+}
+
+impl Display for CharBoundaryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "byte index {} is not a char boundary", self.index)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CharBoundaryError {}
+
+/// The error returned when converting bytes into a [`WriteBuffer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FromBytesError {
+    /// The bytes are not valid UTF-8.
+    Utf8(core::str::Utf8Error),
+    /// The bytes don't fit in the buffer.
+    Capacity(CapacityError),
+}
+
+impl Display for FromBytesError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            FromBytesError::Utf8(e) => write!(f, "invalid UTF-8: {}", e),
+            FromBytesError::Capacity(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FromBytesError {}
+
+/// The error returned by [`WriteBuffer::split_off`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitError {
+    /// The index is past the written content.
+    OutOfBounds {
+        /// The offending byte index.
+        index: usize,
+        /// The length of the written content.
+        len: usize,
+    },
+    /// The index is inside a multi-byte character.
+    NotCharBoundary(CharBoundaryError),
+}
+
+impl Display for SplitError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SplitError::OutOfBounds { index, len } => {
+                write!(f, "byte index {} is out of bounds of length {}", index, len)
+            }
+            SplitError::NotCharBoundary(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SplitError {}
+
+/// The error returned by [`WriteBuffer::insert_str`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InsertError {
+    /// The index is past the written content.
+    OutOfBounds {
+        /// The offending byte index.
+        index: usize,
+        /// The length of the written content.
+        len: usize,
+    },
+    /// The index is inside a multi-byte character.
+    NotCharBoundary(CharBoundaryError),
+    /// The inserted string doesn't fit in the buffer.
+    Capacity(CapacityError),
+}
+
+impl Display for InsertError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            InsertError::OutOfBounds { index, len } => {
+                write!(f, "byte index {} is out of bounds of length {}", index, len)
+            }
+            InsertError::NotCharBoundary(e) => write!(f, "{}", e),
+            InsertError::Capacity(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for InsertError {}
+
+/// The error returned when a byte range doesn't select a valid part of the
+/// written content, see [`WriteBuffer::remove_range`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RangeError {
+    /// The range starts after it ends.
+    StartAfterEnd {
+        /// The start of the range.
+        start: usize,
+        /// The end of the range.
+        end: usize,
+    },
+    /// The range ends past the written content.
+    OutOfBounds {
+        /// The end of the range.
+        end: usize,
+        /// The length of the written content.
+        len: usize,
+    },
+    /// One of the endpoints is inside a multi-byte character.
+    NotCharBoundary(CharBoundaryError),
+}
+
+impl Display for RangeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            RangeError::StartAfterEnd { start, end } => {
+                write!(f, "range starts at {} but ends at {}", start, end)
+            }
+            RangeError::OutOfBounds { end, len } => {
+                write!(f, "range end {} is out of bounds of length {}", end, len)
+            }
+            RangeError::NotCharBoundary(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for RangeError {}
+
+/// The error returned by [`WriteBuffer::from_array`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FromArrayError {
+    /// The given length is greater than the array.
+    OutOfBounds {
+        /// The given length.
+        len: usize,
+        /// The length of the array.
+        capacity: usize,
+    },
+    /// The bytes before the given length are not valid UTF-8.
+    Utf8(core::str::Utf8Error),
+}
+
+impl Display for FromArrayError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            FromArrayError::OutOfBounds { len, capacity } => {
+                write!(f, "length {} is greater than capacity {}", len, capacity)
+            }
+            FromArrayError::Utf8(e) => write!(f, "invalid UTF-8: {}", e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FromArrayError {}
+
+/// The error returned by [`WriteBuffer::set_cursor`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetCursorError {
+    /// The position is past the written content.
+    OutOfBounds {
+        /// The offending byte index.
+        index: usize,
+        /// The length of the written content.
+        len: usize,
+    },
+    /// The position is inside a multi-byte character.
+    NotCharBoundary(CharBoundaryError),
+}
+
+impl Display for SetCursorError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SetCursorError::OutOfBounds { index, len } => {
+                write!(f, "byte index {} is out of bounds of length {}", index, len)
+            }
+            SetCursorError::NotCharBoundary(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SetCursorError {}
+
+/// The error returned by [`WriteBuffer::replace_range`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplaceError {
+    /// The range doesn't select a valid part of the written content.
+    Range(RangeError),
+    /// The replacement doesn't fit, `available` counts the bytes of the
+    /// replaced range too.
+    Capacity(CapacityError),
+}
+
+impl Display for ReplaceError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplaceError::Range(e) => write!(f, "{}", e),
+            ReplaceError::Capacity(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ReplaceError {}
+
+/// The error returned by [`WriteBuffer::keep_tail`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeepTailError {
+    /// The start of the tail is inside a multi-byte character.
+    NotCharBoundary(CharBoundaryError),
+}
+
+impl Display for KeepTailError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            KeepTailError::NotCharBoundary(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for KeepTailError {}
+
+/// The error returned by [`WriteBuffer::as_cstr`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CStrError {
+    /// The written content contains a NUL byte.
+    InteriorNul {
+        /// The byte index of the first NUL.
+        index: usize,
+    },
+    /// There is no room for the NUL terminator.
+    Capacity(CapacityError),
+}
+
+impl Display for CStrError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            CStrError::InteriorNul { index } => write!(f, "NUL byte found at index {}", index),
+            CStrError::Capacity(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CStrError {}
+
+/// The error returned by [`WriteBuffer::write_centered`] and
+/// [`WriteBuffer::write_u32_padded`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PadError {
+    /// The fill character is more than one byte long, which would break the
+    /// width math.
+    MultiByteFill(char),
+    /// The padded field doesn't fit.
+    Capacity(CapacityError),
+}
+
+impl Display for PadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            PadError::MultiByteFill(c) => write!(f, "fill character {:?} is not a single byte", c),
+            PadError::Capacity(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PadError {}
+
+/// What happens when a `core::fmt::Write` write does not fit in the remaining
+/// space of a [`WriteBuffer`].
+///
+/// The policy only affects the `core::fmt::Write` implementation, inherent
+/// methods like [`WriteBuffer::push_str`] always fail on overflow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// The write fails with `fmt::Error` and nothing is written.
+    Fail,
+    /// As many whole characters as fit are written, the rest is dropped and
+    /// the write succeeds. Later writes are dropped too until the buffer is
+    /// reset, so that the content is always a prefix of what was written.
+    Truncate,
+    /// The oldest content is discarded to make room, like in a ring buffer, so
+    /// that the buffer holds the most recent bytes written. Only whole
+    /// characters are kept: if the kept part would start inside a multi-byte
+    /// character, that character is dropped too. If the string is longer than
+    /// the whole buffer, only its last characters that fit are kept.
+    ///
+    /// Discarded content can't be restored, so a `write!` whose formatting
+    /// fails after wrapping keeps the output written so far.
+    Wrap,
+}
+
+/// The alignment of a string within a fixed-width field, see
+/// [`WriteBuffer::write_padded`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    /// Padding goes after the string.
+    Left,
+    /// Padding goes before the string.
+    Right,
+    /// Padding is split around the string, with the extra fill character, if
+    /// any, after it.
+    Center,
+}
+
+/// A snapshot of the length of a [`WriteBuffer`], see
+/// [`WriteBuffer::checkpoint`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checkpoint(usize);
 
 /// A write buffer
-#[derive(Debug, Clone, Copy)]
+///
+/// The buffer dereferences to the written content as a `str`, so `str` methods
+/// such as `trim` or `split` can be called on it directly and `&buffer` can be
+/// passed where a `&str` is expected. Inherent methods like [`len`](Self::len)
+/// take priority over the `str` ones.
+///
+/// The buffer is `Copy`, which makes small buffers cheap to pass around by
+/// value. Note that a copy always moves the whole `[u8; N]` backing array, so
+/// large buffers are better passed by reference.
+#[derive(Clone, Copy)]
 pub struct WriteBuffer<const N: usize> {
     buffer: [u8; N],
     cursor: usize,
+    /// The largest length reached, not cleared by a reset
+    high_water: usize,
+    /// Whether raw bytes, not necessarily valid UTF-8, have been written since
+    /// the last reset
+    raw: bool,
+    /// Whether a `core::fmt::Write` write dropped part of its input since the
+    /// last reset
+    truncated: bool,
+    policy: OverflowPolicy,
 }
 
 impl<const N: usize> WriteBuffer<N> {
     /// Creates a write buffer
-    pub fn new() -> Self {
+    pub const fn new() -> Self {
+        Self::with_policy(OverflowPolicy::Fail)
+    }
+
+    /// Creates a write buffer with the given overflow policy
+    pub const fn with_policy(policy: OverflowPolicy) -> Self {
         let buf = [0u8; N];
         WriteBuffer {
             buffer: buf,
             cursor: 0,
+            high_water: 0,
+            raw: false,
+            truncated: false,
+            policy,
+        }
+    }
+
+    /// Wraps an already populated array, whose first `len` bytes are the
+    /// content.
+    ///
+    /// Fails if `len` is greater than `N` or the content is not valid UTF-8.
+    pub fn from_array(buffer: [u8; N], len: usize) -> Result<Self, FromArrayError> {
+        let content = buffer
+            .get(..len)
+            .ok_or(FromArrayError::OutOfBounds { len, capacity: N })?;
+        core::str::from_utf8(content).map_err(FromArrayError::Utf8)?;
+        Ok(WriteBuffer {
+            buffer,
+            cursor: len,
+            high_water: len,
+            raw: false,
+            truncated: false,
+            policy: OverflowPolicy::Fail,
+        })
+    }
+
+    /// Creates a buffer containing the chars of `iter`, failing if they don't
+    /// fit.
+    ///
+    /// `FromIterator` is not implemented since it would have to panic on
+    /// overflow, this fallible constructor should be used instead.
+    pub fn try_from_chars<I: IntoIterator<Item = char>>(iter: I) -> Result<Self, CapacityError> {
+        let mut buffer = Self::new();
+        for c in iter {
+            buffer.push(c)?;
+        }
+        Ok(buffer)
+    }
+
+    /// Creates a buffer containing the concatenation of the strings of `iter`,
+    /// failing if they don't fit.
+    ///
+    /// Like [`Self::try_from_chars`], this replaces a `FromIterator`
+    /// implementation that would have to panic on overflow.
+    pub fn try_from_strs<'a, I: IntoIterator<Item = &'a str>>(
+        iter: I,
+    ) -> Result<Self, CapacityError> {
+        let mut buffer = Self::new();
+        for s in iter {
+            buffer.push_str(s)?;
+        }
+        Ok(buffer)
+    }
+
+    /// Creates a buffer containing as much of `s` as fits, never splitting a
+    /// multi-byte character, see [`Self::push_str_truncated`].
+    pub fn with_content_truncated(s: &str) -> Self {
+        let mut buffer = Self::new();
+        buffer.push_str_truncated(s);
+        buffer
+    }
+
+    /// Creates a buffer containing the concatenation of `parts`, whose total
+    /// length must be `N`, used by [`ewrite_buffer!`].
+    #[doc(hidden)]
+    pub const fn __from_parts(parts: &[&str]) -> Self {
+        let mut buffer = [0u8; N];
+        let mut cursor = 0;
+        let mut i = 0;
+        while i < parts.len() {
+            let bytes = parts[i].as_bytes();
+            let mut j = 0;
+            while j < bytes.len() {
+                buffer[cursor] = bytes[j];
+                cursor += 1;
+                j += 1;
+            }
+            i += 1;
         }
+        assert!(cursor == N, "the parts length is not the capacity");
+        WriteBuffer {
+            buffer,
+            cursor,
+            high_water: cursor,
+            raw: false,
+            truncated: false,
+            policy: OverflowPolicy::Fail,
+        }
+    }
+
+    /// Returns the policy applied when a `core::fmt::Write` write overflows.
+    pub const fn policy(&self) -> OverflowPolicy {
+        self.policy
+    }
+
+    /// Sets the policy applied when a `core::fmt::Write` write overflows.
+    pub fn set_policy(&mut self, policy: OverflowPolicy) {
+        self.policy = policy;
+    }
+
+    /// Returns true if, since the last reset, a `core::fmt::Write` write
+    /// didn't fit and was cut by the [`OverflowPolicy::Truncate`] or
+    /// [`OverflowPolicy::Wrap`] policy, for example to show an ellipsis.
+    pub const fn truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Clears the flag returned by [`truncated`](Self::truncated), without
+    /// changing the content. With the [`OverflowPolicy::Truncate`] policy
+    /// this also lets the following writes through again.
+    pub fn clear_truncated_flag(&mut self) {
+        self.truncated = false;
+    }
+
+    /// Consumes the buffer, returning the backing array and the number of
+    /// written bytes at its start.
+    ///
+    /// The array is a copy: wiping the buffer, for example through
+    /// `zeroize::Zeroizing`, doesn't wipe it.
+    pub fn into_inner(self) -> ([u8; N], usize) {
+        (self.buffer, self.cursor)
+    }
+
+    /// Copies the written content into a new buffer of capacity `M`, failing
+    /// if it doesn't fit.
+    ///
+    /// The overflow policy is carried over to the new buffer.
+    pub fn resize_into<const M: usize>(&self) -> Result<WriteBuffer<M>, CapacityError> {
+        let mut resized = WriteBuffer::with_policy(self.policy);
+        resized.push_bytes(self.as_slice())?;
+        resized.raw = self.raw;
+        Ok(resized)
     }
 
     /// Returns a slice containing the already written bytes in the buffer
@@ -40,6 +610,37 @@ impl<const N: usize> WriteBuffer<N> {
         &self.buffer[..self.cursor]
     }
 
+    /// Returns a pointer to the start of the written bytes, to pass them
+    /// together with [`len`](Self::len) over FFI.
+    ///
+    /// The pointer is valid only as long as the buffer is alive and not
+    /// modified. The content is not NUL-terminated, unless a `'\0'` was
+    /// written at its end.
+    pub const fn as_ptr(&self) -> *const u8 {
+        self.buffer.as_ptr()
+    }
+
+    /// Returns the written content as a NUL-terminated C string, for FFI.
+    ///
+    /// The terminator is written in the byte right after the content, which
+    /// is not changed: the cursor doesn't move. Fails if the content contains
+    /// a NUL byte or if the buffer is full.
+    pub fn as_cstr(&mut self) -> Result<&CStr, CStrError> {
+        if let Some(index) = self.as_slice().iter().position(|&b| b == 0) {
+            return Err(CStrError::InteriorNul { index });
+        }
+        if self.is_full() {
+            return Err(CStrError::Capacity(CapacityError {
+                requested: 1,
+                available: 0,
+            }));
+        }
+        self.buffer[self.cursor] = 0;
+        let bytes = &self.buffer[..=self.cursor];
+        // SAFETY: `bytes` ends with the NUL just written and has no other NUL
+        Ok(unsafe { CStr::from_bytes_with_nul_unchecked(bytes) })
+    }
+
     /// Returns a mutable slice containing the already written bytes in the
     /// buffer
     ///
@@ -50,151 +651,4182 @@ impl<const N: usize> WriteBuffer<N> {
         &mut self.buffer[..self.cursor]
     }
 
+    /// Returns a mutable slice containing the already written bytes in the
+    /// buffer, for in-place binary manipulation.
+    ///
+    /// [`modify_bytes`](Self::modify_bytes) is the safe alternative, checking
+    /// the content afterwards.
+    ///
+    /// # Safety
+    ///
+    /// The caller must guarantee that, when the returned slice is no longer
+    /// used, the written content is still valid UTF-8:
+    /// [`as_str`](Self::as_str) and everything built on it rely on this
+    /// without checking.
+    pub unsafe fn as_mut_slice_unchecked(&mut self) -> &mut [u8] {
+        self.as_slice_mut()
+    }
+
+    /// Returns a mutable slice over the unwritten part of the buffer.
+    ///
+    /// Bytes written here are not part of the content until the cursor is
+    /// moved over them with [`advance`](Self::advance), for example after a
+    /// DMA transfer filled them.
+    pub fn remaining_mut(&mut self) -> &mut [u8] {
+        &mut self.buffer[self.cursor..]
+    }
+
+    /// Moves the cursor forward by `n` bytes, making them part of the content.
+    ///
+    /// # Safety
+    ///
+    /// The caller must guarantee that, after the move, the written content is
+    /// valid UTF-8: [`as_str`](Self::as_str) and everything built on it rely
+    /// on this without checking. Usually this means the first `n` bytes of
+    /// [`remaining_mut`](Self::remaining_mut) have been filled with valid
+    /// UTF-8.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is greater than [`remaining`](Self::remaining).
+    pub unsafe fn advance(&mut self, n: usize) {
+        assert!(n <= self.remaining(), "advance past the end of the buffer");
+        self.grow_to(self.cursor + n);
+    }
+
+    /// Returns the whole backing array, including the stale bytes past the
+    /// cursor left over by [`Self::reset`].
+    ///
+    /// This is meant for tests and diagnostics only, so it is available only
+    /// with the `debug-raw` feature: the stale bytes may be secrets that were
+    /// meant to be forgotten.
+    #[cfg(any(test, feature = "debug-raw"))]
+    pub fn debug_raw(&self) -> &[u8; N] {
+        &self.buffer
+    }
+
     /// Reset the buffer such that it can be reused.
     ///
     /// Note: This does _not_ overwrite any data in memory, it only sets the
-    /// internal cursor back to the start of the buffer.
+    /// internal cursor back to the start of the buffer. Use [`clear`] to also
+    /// wipe the written bytes.
+    ///
+    /// [`clear`]: Self::clear
     pub fn reset(&mut self) {
         self.cursor = 0;
+        self.raw = false;
+        self.truncated = false;
+    }
+
+    /// Zeroes the written bytes and resets the buffer such that it can be
+    /// reused.
+    ///
+    /// Unlike [`reset`](Self::reset), the bytes are overwritten with volatile
+    /// writes that are not optimized away, which makes this suitable for
+    /// buffers that held sensitive data.
+    pub fn clear(&mut self) {
+        for byte in self.buffer[..self.cursor].iter_mut() {
+            // SAFETY: `byte` is a valid, aligned and exclusive reference
+            unsafe { core::ptr::write_volatile(byte, 0) };
+        }
+        core::sync::atomic::compiler_fence(core::sync::atomic::Ordering::SeqCst);
+        self.reset();
+    }
+
+    /// Shortens the buffer to `new_len` bytes.
+    ///
+    /// If `new_len` is greater than or equal to the current length this has no
+    /// effect.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `new_len` does not lie on a char boundary, see
+    /// [`try_truncate`](Self::try_truncate) for a non-panicking version.
+    pub fn truncate(&mut self, new_len: usize) {
+        if let Err(e) = self.try_truncate(new_len) {
+            panic!("{}", e);
+        }
+    }
+
+    /// Shortens the buffer to `new_len` bytes, failing if `new_len` does not
+    /// lie on a char boundary.
+    ///
+    /// If `new_len` is greater than or equal to the current length this has no
+    /// effect.
+    pub fn try_truncate(&mut self, new_len: usize) -> Result<(), CharBoundaryError> {
+        if new_len >= self.cursor {
+            return Ok(());
+        }
+        if !self.is_char_boundary(new_len) {
+            return Err(CharBoundaryError { index: new_len });
+        }
+        self.cursor = new_len;
+        Ok(())
+    }
+
+    /// Returns the position where the next write goes, that is [`Self::len`].
+    pub const fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Returns the largest length the buffer ever reached, across resets,
+    /// handy to right-size `N` during development.
+    ///
+    /// [`Self::reset`] doesn't clear it, see [`Self::reset_high_water`]. All
+    /// or nothing writes that fail, like [`Self::write_all_str`], don't count.
+    pub const fn max_len_seen(&self) -> usize {
+        self.high_water
+    }
+
+    /// Restarts tracking [`Self::max_len_seen`] from the current length.
+    pub fn reset_high_water(&mut self) {
+        self.high_water = self.cursor;
+    }
+
+    /// Moves the cursor back to `pos`, discarding the content after it.
+    ///
+    /// The cursor is never moved forward over unwritten bytes: fails if `pos`
+    /// is past the written content or if it does not lie on a char boundary.
+    pub fn set_cursor(&mut self, pos: usize) -> Result<(), SetCursorError> {
+        if pos > self.cursor {
+            return Err(SetCursorError::OutOfBounds {
+                index: pos,
+                len: self.cursor,
+            });
+        }
+        if !self.is_char_boundary(pos) {
+            return Err(SetCursorError::NotCharBoundary(CharBoundaryError {
+                index: pos,
+            }));
+        }
+        self.cursor = pos;
+        Ok(())
+    }
+
+    /// Returns a checkpoint of the current length, to later go back to it
+    /// with [`restore`](Self::restore).
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint(self.cursor)
+    }
+
+    /// Discards everything written after `checkpoint` was taken.
+    ///
+    /// The cursor only ever moves backward: if the buffer is already shorter
+    /// than the checkpoint, for example after a [`reset`](Self::reset), this
+    /// has no effect.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `checkpoint` was taken from another buffer and doesn't lie on
+    /// a char boundary of this one.
+    pub fn restore(&mut self, checkpoint: Checkpoint) {
+        self.truncate(checkpoint.0);
+    }
+
+    /// Starts a transaction: the writes made through the returned guard are
+    /// discarded when it is dropped, unless [`Transaction::commit`] is called.
+    pub fn transaction(&mut self) -> Transaction<'_, N> {
+        Transaction {
+            checkpoint: self.checkpoint(),
+            buffer: self,
+        }
     }
 
     /// Converts the buffer into `&str`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if raw bytes that are not valid UTF-8 have been written into the
+    /// buffer, for example through [`write_bytes`](Self::write_bytes). Use
+    /// [`try_as_str`](Self::try_as_str) when that might be the case.
     pub fn as_str(&self) -> &str {
-        // SAFETY: The only way to write into `self.buf` is via
-        // `Write::write_str`. Therefore it is always guaranteed that the buffer
+        self.try_as_str().expect("buffer contains invalid UTF-8")
+    }
+
+    /// Converts the buffer into `&str`, failing if raw bytes that are not
+    /// valid UTF-8 have been written into it.
+    ///
+    /// The content is only validated if raw bytes have been written since the
+    /// last reset, otherwise this is as cheap as [`as_str`](Self::as_str).
+    pub fn try_as_str(&self) -> Result<&str, core::str::Utf8Error> {
+        if self.raw {
+            return core::str::from_utf8(self.as_slice());
+        }
+        // SAFETY: Unless `self.raw` is set, only whole `str`s have been written
+        // into `self.buffer`. Therefore it is always guaranteed that the buffer
         // contains valid UTF-8.
-        unsafe { core::str::from_utf8_unchecked(self.as_slice()) }
+        Ok(unsafe { core::str::from_utf8_unchecked(self.as_slice()) })
+    }
+
+    /// Converts the buffer into `&str`, always validating the content with
+    /// `core::str::from_utf8`, without relying on any `unsafe` code.
+    ///
+    /// Meant as a defensive check of the buffer invariant, for example in
+    /// debug assertions, [`try_as_str`](Self::try_as_str) is the faster
+    /// equivalent.
+    pub fn as_str_checked(&self) -> Result<&str, core::str::Utf8Error> {
+        core::str::from_utf8(self.as_slice())
     }
 
     /// Converts the buffer into `&mut str`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if raw bytes that are not valid UTF-8 have been written into the
+    /// buffer, for example through [`write_bytes`](Self::write_bytes).
     pub fn as_str_mut(&mut self) -> &mut str {
-        // SAFETY: The only way to write into `self.buf` is via
-        // `Write::write_str`. Therefore it is always guaranteed that the buffer
+        if self.raw {
+            return core::str::from_utf8_mut(self.as_slice_mut())
+                .expect("buffer contains invalid UTF-8");
+        }
+        // SAFETY: Unless `self.raw` is set, only whole `str`s have been written
+        // into `self.buffer`. Therefore it is always guaranteed that the buffer
         // contains valid UTF-8.
         unsafe { core::str::from_utf8_unchecked_mut(self.as_slice_mut()) }
     }
 
+    /// Returns true if byte index `idx` is the start of a character of the
+    /// written content, or its end.
+    ///
+    /// Indices past the written content return false. Same as
+    /// `str::is_char_boundary`, but it also works on raw bytes that are not
+    /// valid UTF-8.
+    pub fn is_char_boundary(&self, idx: usize) -> bool {
+        if idx == 0 {
+            return true;
+        }
+        match self.as_slice().get(idx) {
+            // Continuation bytes have the form `0b10xx_xxxx`
+            Some(&b) => (b as i8) >= -0x40,
+            None => idx == self.cursor,
+        }
+    }
+
+    /// Appends as many of `bytes` as fit in the remaining space, returning the
+    /// number of bytes written.
+    ///
+    /// The bytes might not be valid UTF-8, so from now on the content is
+    /// validated when converted into `&str`.
+    #[cfg(any(feature = "embedded-io", feature = "std"))]
+    fn push_raw_truncated(&mut self, bytes: &[u8]) -> usize {
+        let n = bytes.len().min(self.remaining());
+        self.buffer[self.cursor..self.cursor + n].copy_from_slice(&bytes[..n]);
+        self.grow_to(self.cursor + n);
+        self.raw |= n > 0;
+        n
+    }
+
+    /// Checks at compile time that the buffer can hold at least `MIN` bytes.
+    ///
+    /// Evaluating this with `N < MIN` is a compile error rather than a runtime
+    /// failure. This is handy when the content has a known minimum length,
+    /// for example a fixed template: put the check in a constant next to the
+    /// buffer declaration, so that shrinking the buffer below the template
+    /// length doesn't build.
+    ///
+    /// ```
+    /// use e_write_buffer::WriteBuffer;
+    ///
+    /// const TEMPLATE: &str = "temperature: ";
+    /// type Line = WriteBuffer<20>;
+    /// const _: () = Line::assert_fits::<{ TEMPLATE.len() }>();
+    /// ```
+    ///
+    /// When called in a regular function instead, the error is only reported
+    /// when the function is actually compiled to code, for example by
+    /// `cargo build` but not by `cargo check`.
+    pub const fn assert_fits<const MIN: usize>() {
+        let () = AssertFits::<N, MIN>::OK;
+    }
+
+    /// Returns the total number of bytes the buffer can hold, that is `N`.
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
     /// Returns how many bytes in the buffer have already been written.
-    pub fn len(&self) -> usize {
+    ///
+    /// This is a number of bytes, not of characters, see
+    /// [`count_chars`](Self::count_chars).
+    pub const fn len(&self) -> usize {
         self.cursor
     }
 
     /// Returns true if zero bytes in the buffer are written.
-    pub fn is_empty(&self) -> bool {
+    pub const fn is_empty(&self) -> bool {
         self.len() == 0
     }
 
     /// Returns how many bytes in the buffer remain for writing.
-    pub fn remaining(&self) -> usize {
+    pub const fn remaining(&self) -> usize {
         N - self.len()
     }
 
     /// Returns true if the buffer is full.
-    pub fn is_full(&self) -> bool {
+    pub const fn is_full(&self) -> bool {
         self.remaining() == 0
     }
-}
 
-impl<const N: usize> Default for WriteBuffer<N> {
-    fn default() -> Self {
-        Self::new()
+    /// Returns true if `additional` more bytes fit in the remaining space,
+    /// for example to check before running an expensive format.
+    pub const fn can_fit(&self, additional: usize) -> bool {
+        self.remaining() >= additional
     }
-}
 
-impl<const N: usize> PartialEq for WriteBuffer<N> {
-    fn eq(&self, other: &Self) -> bool {
-        self.as_slice() == other.as_slice()
+    /// Returns how many bytes are missing to write `additional` more bytes,
+    /// zero if they fit.
+    pub const fn shortfall(&self, additional: usize) -> usize {
+        additional.saturating_sub(self.remaining())
     }
-}
 
-impl<const N: usize> core::hash::Hash for WriteBuffer<N> {
-    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
-        self.as_slice().hash(state);
+    /// Returns an iterator over the written bytes.
+    pub fn bytes(&self) -> impl Iterator<Item = u8> + '_ {
+        self.as_slice().iter().copied()
     }
-}
 
-impl<const N: usize> Eq for WriteBuffer<N> {}
+    /// Returns an iterator over the chars of the written content.
+    ///
+    /// # Panics
+    ///
+    /// If the content is not valid UTF-8, see [`Self::as_str`].
+    pub fn chars(&self) -> core::str::Chars<'_> {
+        self.as_str().chars()
+    }
+
+    /// Returns the number of chars in the written content, while
+    /// [`len`](Self::len) is the number of bytes.
+    ///
+    /// # Panics
+    ///
+    /// If the content is not valid UTF-8, see [`Self::as_str`].
+    pub fn count_chars(&self) -> usize {
+        self.chars().count()
+    }
+
+    /// Returns an iterator over the chars of the written content and their
+    /// byte offsets, as `str::char_indices`.
+    ///
+    /// # Panics
+    ///
+    /// If the content is not valid UTF-8, see [`Self::as_str`].
+    pub fn char_indices(&self) -> core::str::CharIndices<'_> {
+        self.as_str().char_indices()
+    }
+
+    /// Returns an iterator over the lines of the written content, as
+    /// `str::lines`.
+    ///
+    /// # Panics
+    ///
+    /// If the content is not valid UTF-8, see [`Self::as_str`].
+    pub fn lines(&self) -> core::str::Lines<'_> {
+        self.as_str().lines()
+    }
+
+    /// Returns the number of lines in the written content.
+    ///
+    /// An empty buffer has no lines, and a trailing newline doesn't start a
+    /// new line.
+    ///
+    /// # Panics
+    ///
+    /// If the content is not valid UTF-8, see [`Self::as_str`].
+    pub fn line_count(&self) -> usize {
+        self.lines().count()
+    }
+
+    /// Returns an iterator over the pieces of the written content separated
+    /// by `sep`, as `str::split`.
+    ///
+    /// Use [`as_str`](Self::as_str) for patterns other than `&str`.
+    ///
+    /// # Panics
+    ///
+    /// If the content is not valid UTF-8, see [`Self::as_str`].
+    pub fn split<'a>(&'a self, sep: &'a str) -> core::str::Split<'a, &'a str> {
+        self.as_str().split(sep)
+    }
+
+    /// Returns an iterator over the pieces of the written content separated
+    /// by `sep`, starting from the end, as `str::rsplit`.
+    ///
+    /// Use [`as_str`](Self::as_str) for patterns other than `&str`.
+    ///
+    /// # Panics
+    ///
+    /// If the content is not valid UTF-8, see [`Self::as_str`].
+    pub fn rsplit<'a>(&'a self, sep: &'a str) -> core::str::RSplit<'a, &'a str> {
+        self.as_str().rsplit(sep)
+    }
+
+    /// Returns the byte offset of the first occurrence of `pat` in the
+    /// written content.
+    ///
+    /// Use [`as_str`](Self::as_str) for patterns other than `&str`.
+    ///
+    /// # Panics
+    ///
+    /// If the content is not valid UTF-8, see [`Self::as_str`].
+    pub fn find(&self, pat: &str) -> Option<usize> {
+        self.as_str().find(pat)
+    }
+
+    /// Returns the byte offset of the last occurrence of `pat` in the written
+    /// content.
+    ///
+    /// Use [`as_str`](Self::as_str) for patterns other than `&str`.
+    ///
+    /// # Panics
+    ///
+    /// If the content is not valid UTF-8, see [`Self::as_str`].
+    pub fn rfind(&self, pat: &str) -> Option<usize> {
+        self.as_str().rfind(pat)
+    }
+
+    /// Returns the first character of the written content, or `None` if the
+    /// buffer is empty.
+    ///
+    /// # Panics
+    ///
+    /// If the content is not valid UTF-8, see [`Self::as_str`].
+    pub fn first_char(&self) -> Option<char> {
+        self.as_str().chars().next()
+    }
+
+    /// Returns the last character of the written content, or `None` if the
+    /// buffer is empty.
+    ///
+    /// # Panics
+    ///
+    /// If the content is not valid UTF-8, see [`Self::as_str`].
+    pub fn last_char(&self) -> Option<char> {
+        self.as_str().chars().next_back()
+    }
+
+    /// Returns the written byte at index `idx`, or `None` if it is past the
+    /// written content.
+    pub fn byte_at(&self, idx: usize) -> Option<u8> {
+        self.as_slice().get(idx).copied()
+    }
+
+    /// Returns the character starting at byte index `idx`, or `None` if `idx`
+    /// is past the written content or not on a char boundary.
+    ///
+    /// # Panics
+    ///
+    /// If the content is not valid UTF-8, see [`Self::as_str`].
+    pub fn char_at(&self, idx: usize) -> Option<char> {
+        self.as_str().get(idx..)?.chars().next()
+    }
+
+    /// Splits the written content in two at byte index `mid`.
+    ///
+    /// Unlike `str::split_at` this doesn't panic, returning `None` if `mid` is
+    /// past the written content or not on a char boundary.
+    ///
+    /// # Panics
+    ///
+    /// If the content is not valid UTF-8, see [`Self::as_str`].
+    pub fn split_at(&self, mid: usize) -> Option<(&str, &str)> {
+        let s = self.as_str();
+        Some((s.get(..mid)?, s.get(mid..)?))
+    }
+
+    /// Returns true if the written content starts with `pat`.
+    ///
+    /// Use [`as_str`](Self::as_str) for patterns other than `&str`.
+    ///
+    /// # Panics
+    ///
+    /// If the content is not valid UTF-8, see [`Self::as_str`].
+    pub fn starts_with(&self, pat: &str) -> bool {
+        self.as_str().starts_with(pat)
+    }
+
+    /// Returns true if the written content ends with `pat`.
+    ///
+    /// Use [`as_str`](Self::as_str) for patterns other than `&str`.
+    ///
+    /// # Panics
+    ///
+    /// If the content is not valid UTF-8, see [`Self::as_str`].
+    pub fn ends_with(&self, pat: &str) -> bool {
+        self.as_str().ends_with(pat)
+    }
+
+    /// Returns true if the written content contains `pat`.
+    ///
+    /// Use [`as_str`](Self::as_str) for patterns other than `&str`.
+    ///
+    /// # Panics
+    ///
+    /// If the content is not valid UTF-8, see [`Self::as_str`].
+    pub fn contains(&self, pat: &str) -> bool {
+        self.as_str().contains(pat)
+    }
+
+    /// Appends `s` to the buffer.
+    ///
+    /// If `s` does not fit in the remaining space nothing is written and the
+    /// returned error reports how many bytes were requested and how many were
+    /// available.
+    pub fn push_str(&mut self, s: &str) -> Result<(), CapacityError> {
+        self.push_bytes(s.as_bytes())
+    }
+
+    /// Appends raw `bytes` to the buffer.
+    ///
+    /// The bytes are not required to be valid UTF-8, for example when
+    /// assembling a binary frame. Once raw bytes have been written, and until
+    /// the next reset, the content is validated when converted into `&str`:
+    /// use [`try_as_str`](Self::try_as_str) to get an error instead of a panic.
+    ///
+    /// If `bytes` do not fit in the remaining space nothing is written.
+    pub fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), CapacityError> {
+        self.push_bytes(bytes)?;
+        self.raw |= !bytes.is_empty();
+        Ok(())
+    }
+
+    /// Returns the cursor after writing `additional` more bytes, or `None` if
+    /// they don't fit.
+    ///
+    /// The addition is checked: on targets with a small `usize` a huge length
+    /// must not wrap around and pass the capacity check.
+    fn cursor_after(&self, additional: usize) -> Option<usize> {
+        match self.cursor.checked_add(additional) {
+            Some(new_cursor) if new_cursor <= N => Some(new_cursor),
+            _ => None,
+        }
+    }
+
+    /// Moves the cursor to `new_cursor`, updating the high-water mark.
+    fn grow_to(&mut self, new_cursor: usize) {
+        self.cursor = new_cursor;
+        self.high_water = self.high_water.max(new_cursor);
+    }
+
+    /// Undoes a failed all-or-nothing write, going back to the length and
+    /// high-water mark the buffer had before it.
+    fn roll_back(&mut self, cursor: usize, high_water: usize) {
+        self.cursor = cursor;
+        self.high_water = high_water;
+    }
+
+    /// Appends `bytes` if they fit in the remaining space.
+    ///
+    /// Callers must set `self.raw` if `bytes` might not be valid UTF-8.
+    fn push_bytes(&mut self, bytes: &[u8]) -> Result<(), CapacityError> {
+        // New cursor after write, if we would exceed the capacity of the
+        // buffer, we fail
+        let new_cursor = match self.cursor_after(bytes.len()) {
+            Some(new_cursor) => new_cursor,
+            None => {
+                return Err(CapacityError {
+                    requested: bytes.len(),
+                    available: self.remaining(),
+                })
+            }
+        };
+
+        // Efficiently copy the bytes into the bufffer
+        self.buffer[self.cursor..new_cursor].copy_from_slice(bytes);
+
+        // Update the cursor
+        self.grow_to(new_cursor);
+
+        Ok(())
+    }
+
+    /// Appends as much of `s` as fits in the remaining space, returning the
+    /// number of bytes written.
+    ///
+    /// Only whole characters are written: if a multi-byte character would not
+    /// fit entirely, it is dropped together with the rest of `s`.
+    pub fn push_str_truncated(&mut self, s: &str) -> usize {
+        let mut end = s.len().min(self.remaining());
+        while !s.is_char_boundary(end) {
+            end -= 1;
+        }
+        // Can't fail, `end` is within the remaining space
+        let _ = self.push_str(&s[..end]);
+        end
+    }
+
+    /// Appends the character `c` to the buffer.
+    ///
+    /// If the whole encoded character does not fit nothing is written.
+    pub fn push(&mut self, c: char) -> Result<(), CapacityError> {
+        let mut encoded = [0u8; 4];
+        self.push_str(c.encode_utf8(&mut encoded))
+    }
+
+    /// Appends all the fragments yielded by `iter`.
+    ///
+    /// Either all the fragments are written or, if they don't fit, none is: on
+    /// error the buffer is left unchanged. The iterator is not consumed past
+    /// the first fragment that doesn't fit, so it may be endless, and the error
+    /// reports the length of the fragments up to that one, a lower bound of
+    /// the total.
+    pub fn write_all_str<'a, I: IntoIterator<Item = &'a str>>(
+        &mut self,
+        iter: I,
+    ) -> Result<(), CapacityError> {
+        let start = self.cursor;
+        let high_water = self.high_water;
+        for s in iter {
+            if self.push_str(s).is_err() {
+                let requested = self.cursor - start + s.len();
+                self.roll_back(start, high_water);
+                return Err(CapacityError {
+                    requested,
+                    available: self.remaining(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Appends `c` repeatedly until the buffer is full.
+    ///
+    /// Fails without writing anything if the remaining space is not a multiple
+    /// of the encoded length of `c`, since that would leave room for only part
+    /// of a character. Single-byte characters, like `' '` or `'\0'`, always
+    /// succeed.
+    pub fn fill_remaining(&mut self, c: char) -> Result<(), CapacityError> {
+        let mut encoded = [0u8; 4];
+        let encoded = c.encode_utf8(&mut encoded).as_bytes();
+        let remaining = self.remaining();
+        let leftover = remaining % encoded.len();
+        if leftover != 0 {
+            return Err(CapacityError {
+                requested: remaining - leftover + encoded.len(),
+                available: remaining,
+            });
+        }
+        for chunk in self.buffer[self.cursor..].chunks_exact_mut(encoded.len()) {
+            chunk.copy_from_slice(encoded);
+        }
+        self.grow_to(N);
+        Ok(())
+    }
+
+    /// Appends `s` repeated `n` times.
+    ///
+    /// Either all the repetitions are written or, if they don't fit, none is.
+    /// If the total length overflows `usize`, the error reports `usize::MAX`
+    /// requested bytes.
+    pub fn repeat_str(&mut self, s: &str, n: usize) -> Result<(), CapacityError> {
+        // A saturated length can't fit, so overflow is reported as an error
+        let requested = s.len().saturating_mul(n);
+        let new_cursor = self.cursor_after(requested).ok_or(CapacityError {
+            requested,
+            available: self.remaining(),
+        })?;
+        if !s.is_empty() {
+            for chunk in self.buffer[self.cursor..new_cursor].chunks_exact_mut(s.len()) {
+                chunk.copy_from_slice(s.as_bytes());
+            }
+        }
+        self.grow_to(new_cursor);
+        Ok(())
+    }
+
+    /// Appends all the items yielded by `iter`, with `sep` between them.
+    ///
+    /// Either the whole joined string is written or, if it doesn't fit,
+    /// nothing is.
+    pub fn write_joined<'a, I: IntoIterator<Item = &'a str>>(
+        &mut self,
+        iter: I,
+        sep: &str,
+    ) -> Result<(), CapacityError> {
+        let mut first = true;
+        self.write_all_str(iter.into_iter().flat_map(|s| {
+            let prefix = if first { "" } else { sep };
+            first = false;
+            [prefix, s]
+        }))
+    }
+
+    /// Appends `bytes` encoded as lower-case hex, two digits per byte.
+    ///
+    /// If the encoded bytes don't fit nothing is written.
+    pub fn write_hex_lower(&mut self, bytes: &[u8]) -> Result<(), CapacityError> {
+        self.write_hex(bytes, b"0123456789abcdef")
+    }
+
+    /// Appends `bytes` encoded as upper-case hex, two digits per byte.
+    ///
+    /// If the encoded bytes don't fit nothing is written.
+    pub fn write_hex_upper(&mut self, bytes: &[u8]) -> Result<(), CapacityError> {
+        self.write_hex(bytes, b"0123456789ABCDEF")
+    }
+
+    /// Appends `bytes` encoded as hex, using `digits` for the digit values.
+    fn write_hex(&mut self, bytes: &[u8], digits: &[u8; 16]) -> Result<(), CapacityError> {
+        let requested = bytes.len().saturating_mul(2);
+        let new_cursor = self.cursor_after(requested).ok_or(CapacityError {
+            requested,
+            available: self.remaining(),
+        })?;
+        let out = self.buffer[self.cursor..new_cursor].chunks_exact_mut(2);
+        for (pair, byte) in out.zip(bytes) {
+            pair[0] = digits[usize::from(byte >> 4)];
+            pair[1] = digits[usize::from(byte & 0x0f)];
+        }
+        self.grow_to(new_cursor);
+        Ok(())
+    }
+
+    /// Appends `s` aligned within a field of `width` characters, padded with
+    /// `fill`.
+    ///
+    /// If `s` is already `width` characters or longer it is written as is, it
+    /// is never truncated. Either the whole field is written or, if it doesn't
+    /// fit, nothing is.
+    pub fn write_padded(
+        &mut self,
+        s: &str,
+        width: usize,
+        align: Align,
+        fill: char,
+    ) -> Result<(), CapacityError> {
+        let pad = width.saturating_sub(s.chars().count());
+        let (before, after) = match align {
+            Align::Left => (0, pad),
+            Align::Right => (pad, 0),
+            Align::Center => (pad / 2, pad - pad / 2),
+        };
+        let requested = pad.saturating_mul(fill.len_utf8()).saturating_add(s.len());
+        if requested > self.remaining() {
+            return Err(CapacityError {
+                requested,
+                available: self.remaining(),
+            });
+        }
+        let mut encoded = [0u8; 4];
+        let fill = fill.encode_utf8(&mut encoded);
+        // Can't fail, the whole field fits in the remaining space
+        let _ = self.repeat_str(fill, before);
+        let _ = self.push_str(s);
+        let _ = self.repeat_str(fill, after);
+        Ok(())
+    }
+
+    /// Appends `s` centered within a field of `width` characters, padded with
+    /// `fill`, see [`write_padded`](Self::write_padded).
+    ///
+    /// When the padding can't be split evenly, the extra `fill` goes on the
+    /// right. `fill` must be a single byte, a multi-byte character fails
+    /// without writing anything.
+    pub fn write_centered(&mut self, s: &str, width: usize, fill: char) -> Result<(), PadError> {
+        if fill.len_utf8() != 1 {
+            return Err(PadError::MultiByteFill(fill));
+        }
+        self.write_padded(s, width, Align::Center, fill)
+            .map_err(PadError::Capacity)
+    }
+
+    /// Writes the formatted arguments, like `write!`, but reports the richer
+    /// [`CapacityError`] on overflow.
+    ///
+    /// Either the whole formatted output is written or none is, regardless of
+    /// the overflow policy. On error the arguments are formatted a second time
+    /// to measure how many bytes were requested. The [`ewrite!`] macro wraps
+    /// this method.
+    ///
+    /// # Panics
+    ///
+    /// Like `ToString::to_string`, panics if a formatting trait implementation
+    /// returns an error on its own rather than because the buffer is full. The
+    /// buffer is left unchanged.
+    pub fn write_fmt_checked(&mut self, args: fmt::Arguments<'_>) -> Result<(), CapacityError> {
+        /// Writes through `push_str`, ignoring the overflow policy
+        struct Strict<'a, const N: usize>(&'a mut WriteBuffer<N>);
+
+        impl<const N: usize> fmt::Write for Strict<'_, N> {
+            fn write_str(&mut self, s: &str) -> fmt::Result {
+                self.0.push_str(s).map_err(|_| fmt::Error)
+            }
+        }
+
+        let start = self.cursor;
+        let high_water = self.high_water;
+        if fmt::write(&mut Strict(self), args).is_ok() {
+            return Ok(());
+        }
+        self.roll_back(start, high_water);
+        let mut counter = Counter(0);
+        // `Counter` never fails, and if the output fits the first attempt
+        // didn't fail because of the capacity either
+        if fmt::write(&mut counter, args).is_err() || counter.0 <= self.remaining() {
+            panic!("a formatting trait implementation returned an error");
+        }
+        Err(CapacityError {
+            requested: counter.0,
+            available: self.remaining(),
+        })
+    }
+
+    /// Appends the items yielded by `iter`, formatted with their `Display`
+    /// implementation, with `sep` between them.
+    ///
+    /// Either the whole list is written or, if it doesn't fit, nothing is.
+    /// Like for [`write_all_str`](Self::write_all_str), the items after the
+    /// first one that doesn't fit are not formatted, and the error reports the
+    /// length of the list up to that item.
+    ///
+    /// # Panics
+    ///
+    /// If a `Display` implementation fails on its own, see
+    /// [`write_fmt_checked`](Self::write_fmt_checked).
+    pub fn write_display_list<T: Display, I: IntoIterator<Item = T>>(
+        &mut self,
+        iter: I,
+        sep: &str,
+    ) -> Result<(), CapacityError> {
+        let start = self.cursor;
+        let high_water = self.high_water;
+        let mut first = true;
+        for item in iter {
+            let prefix = if first { "" } else { sep };
+            first = false;
+            if let Err(e) = self.write_fmt_checked(format_args!("{}{}", prefix, item)) {
+                let requested = self.cursor - start + e.requested;
+                self.roll_back(start, high_water);
+                return Err(CapacityError {
+                    requested,
+                    available: self.remaining(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Appends the content of `other`.
+    ///
+    /// If it doesn't fit nothing is written.
+    pub fn concat<const M: usize>(&mut self, other: &WriteBuffer<M>) -> Result<(), CapacityError> {
+        self.push_bytes(other.as_slice())?;
+        self.raw |= other.raw;
+        Ok(())
+    }
+
+    /// Appends `n` in decimal, without going through `core::fmt`.
+    ///
+    /// If the digits don't fit nothing is written.
+    pub fn write_u8(&mut self, n: u8) -> Result<(), CapacityError> {
+        self.write_u32(n.into())
+    }
+
+    /// Appends `n` in decimal, without going through `core::fmt`.
+    ///
+    /// If the digits don't fit nothing is written.
+    pub fn write_u16(&mut self, n: u16) -> Result<(), CapacityError> {
+        self.write_u32(n.into())
+    }
+
+    /// Appends `n` in decimal, without going through `core::fmt`.
+    ///
+    /// If the digits don't fit nothing is written.
+    pub fn write_u32(&mut self, n: u32) -> Result<(), CapacityError> {
+        let mut scratch = [0u8; 10];
+        let start = render_u32(n, &mut scratch);
+        self.push_bytes(&scratch[start..])
+    }
+
+    /// Appends `n` in decimal, without going through `core::fmt`.
+    ///
+    /// If the digits don't fit nothing is written.
+    pub fn write_u64(&mut self, n: u64) -> Result<(), CapacityError> {
+        let mut scratch = [0u8; 20];
+        let start = render_u64(n, &mut scratch);
+        self.push_bytes(&scratch[start..])
+    }
+
+    /// Appends `n` in decimal, without going through `core::fmt`.
+    ///
+    /// If the digits and sign don't fit nothing is written.
+    pub fn write_i8(&mut self, n: i8) -> Result<(), CapacityError> {
+        self.write_i32(n.into())
+    }
+
+    /// Appends `n` in decimal, without going through `core::fmt`.
+    ///
+    /// If the digits and sign don't fit nothing is written.
+    pub fn write_i16(&mut self, n: i16) -> Result<(), CapacityError> {
+        self.write_i32(n.into())
+    }
+
+    /// Appends `n` in decimal, without going through `core::fmt`.
+    ///
+    /// If the digits and sign don't fit nothing is written.
+    pub fn write_i32(&mut self, n: i32) -> Result<(), CapacityError> {
+        let mut scratch = [0u8; 11];
+        let mut start = render_u32(n.unsigned_abs(), &mut scratch);
+        if n < 0 {
+            start -= 1;
+            scratch[start] = b'-';
+        }
+        self.push_bytes(&scratch[start..])
+    }
+
+    /// Appends `n` in decimal, without going through `core::fmt`.
+    ///
+    /// If the digits and sign don't fit nothing is written.
+    pub fn write_i64(&mut self, n: i64) -> Result<(), CapacityError> {
+        let mut scratch = [0u8; 20];
+        let mut start = render_u64(n.unsigned_abs(), &mut scratch);
+        if n < 0 {
+            start -= 1;
+            scratch[start] = b'-';
+        }
+        self.push_bytes(&scratch[start..])
+    }
+
+    /// Appends `s` escaped as the content of a JSON string, the surrounding
+    /// quotes are not written.
+    ///
+    /// Quotes, backslashes and control characters are escaped. Either the
+    /// whole escaped string is written or, if it doesn't fit, nothing is.
+    pub fn write_json_escaped(&mut self, s: &str) -> Result<(), CapacityError> {
+        let mut scratch = [0u8; 6];
+        let requested = s.chars().fold(0usize, |acc, c| {
+            let len = json_escape(c, &mut scratch).map_or(c.len_utf8(), <[u8]>::len);
+            acc.saturating_add(len)
+        });
+        if self.cursor_after(requested).is_none() {
+            return Err(CapacityError {
+                requested,
+                available: self.remaining(),
+            });
+        }
+        let mut utf8 = [0u8; 4];
+        for c in s.chars() {
+            let bytes = match json_escape(c, &mut scratch) {
+                Some(escape) => escape,
+                None => c.encode_utf8(&mut utf8).as_bytes(),
+            };
+            self.push_bytes(bytes)?;
+        }
+        Ok(())
+    }
+
+    /// Appends `x` with `decimals` decimal places, rounding like `{:.N}`
+    /// does, mostly using integer arithmetic instead of `core::fmt`.
+    ///
+    /// Non-finite values, values too large for the integer path and more than
+    /// 12 decimals fall back to [`Self::write_fmt_checked`]. Either the whole
+    /// number is written or, if it doesn't fit, nothing is. More decimals than
+    /// fit in the remaining space, including more than `core::fmt` supports,
+    /// fail without formatting anything.
+    pub fn write_f32(&mut self, x: f32, decimals: usize) -> Result<(), CapacityError> {
+        // Up to 12 decimals `5^decimals` has few enough bits that the product
+        // below is exact in `f64`, so the rounding matches `core::fmt`
+        const MAX_DECIMALS: usize = 12;
+        let negative = x.is_sign_negative();
+        let abs = if negative {
+            -f64::from(x)
+        } else {
+            f64::from(x)
+        };
+        if !x.is_finite() {
+            // The precision doesn't apply to `NaN` and infinities
+            return self.write_fmt_checked(format_args!("{}", x));
+        }
+        if decimals > MAX_DECIMALS {
+            // At least a digit, the point and the decimals. Checking it first
+            // also keeps huge precisions, that make `core::fmt` panic, away
+            // from it
+            let min_len = (usize::from(negative) + 2).saturating_add(decimals);
+            if min_len > self.remaining() {
+                return Err(CapacityError {
+                    requested: min_len,
+                    available: self.remaining(),
+                });
+            }
+        }
+        let scaled = abs * 10u64.pow(decimals.min(MAX_DECIMALS) as u32) as f64;
+        if decimals > MAX_DECIMALS || scaled >= u64::MAX as f64 {
+            return self.write_fmt_checked(format_args!("{:.*}", decimals, x));
+        }
+
+        let mut n = scaled as u64;
+        let fraction = scaled - n as f64;
+        if fraction > 0.5 || (fraction == 0.5 && n % 2 == 1) {
+            n += 1;
+        }
+        let mut digits = [b'0'; 20];
+        let start = render_u64(n, &mut digits).min(digits.len() - decimals - 1);
+        let (int, fract) = digits[start..].split_at(digits.len() - start - decimals);
+
+        let requested = usize::from(negative) + int.len() + usize::from(decimals > 0) + decimals;
+        if self.cursor_after(requested).is_none() {
+            return Err(CapacityError {
+                requested,
+                available: self.remaining(),
+            });
+        }
+        if negative {
+            self.push_bytes(b"-")?;
+        }
+        self.push_bytes(int)?;
+        if decimals > 0 {
+            self.push_bytes(b".")?;
+            self.push_bytes(fract)?;
+        }
+        Ok(())
+    }
+
+    /// Appends `bytes` decoded as UTF-8, replacing invalid sequences with
+    /// U+FFFD like `String::from_utf8_lossy`, so the content stays valid UTF-8.
+    ///
+    /// Either the whole decoded string is written or, if it doesn't fit,
+    /// nothing is.
+    pub fn write_bytes_lossy(&mut self, bytes: &[u8]) -> Result<(), CapacityError> {
+        let mut requested = 0usize;
+        for_each_lossy(bytes, |s| requested = requested.saturating_add(s.len()));
+        if self.cursor_after(requested).is_none() {
+            return Err(CapacityError {
+                requested,
+                available: self.remaining(),
+            });
+        }
+        for_each_lossy(bytes, |s| {
+            let _ = self.push_str(s);
+        });
+        Ok(())
+    }
+
+    /// Appends `true` or `false`, without going through `core::fmt`.
+    ///
+    /// If it doesn't fit nothing is written.
+    pub fn write_bool(&mut self, b: bool) -> Result<(), CapacityError> {
+        self.push_str(if b { "true" } else { "false" })
+    }
+
+    /// Appends `c` repeated `n` times.
+    ///
+    /// Either all the repetitions are written or, if they don't fit, none is.
+    pub fn write_char_repeated(&mut self, c: char, n: usize) -> Result<(), CapacityError> {
+        let mut encoded = [0u8; 4];
+        self.repeat_str(c.encode_utf8(&mut encoded), n)
+    }
+
+    /// Appends `c` until the length is at least `target_len` bytes, for
+    /// example to pad up to a column.
+    ///
+    /// With a multi-byte `c` the length may end up past `target_len`, when the
+    /// missing bytes are not a multiple of its length. Either all the padding
+    /// is written or, if it doesn't fit, none is. If the buffer is already
+    /// long enough this has no effect.
+    pub fn pad_to(&mut self, c: char, target_len: usize) -> Result<(), CapacityError> {
+        let missing = target_len.saturating_sub(self.cursor);
+        let width = c.len_utf8();
+        let count = missing / width + usize::from(missing % width != 0);
+        self.write_char_repeated(c, count)
+    }
+
+    /// Appends `n` in decimal, right-aligned within a field of `width`
+    /// characters padded with `fill`, like `{:0>3}` does, without going
+    /// through `core::fmt`.
+    ///
+    /// Numbers wider than `width` are written as is. `fill` must be a single
+    /// byte, a multi-byte character fails without writing anything. Either
+    /// the whole field is written or, if it doesn't fit, nothing is.
+    pub fn write_u32_padded(&mut self, n: u32, width: usize, fill: char) -> Result<(), PadError> {
+        if fill.len_utf8() != 1 {
+            return Err(PadError::MultiByteFill(fill));
+        }
+        let mut scratch = [0u8; 10];
+        let start = render_u32(n, &mut scratch);
+        // SAFETY: only ASCII digits are rendered
+        let digits = unsafe { core::str::from_utf8_unchecked(&scratch[start..]) };
+        self.write_padded(digits, width, Align::Right, fill)
+            .map_err(PadError::Capacity)
+    }
+
+    /// Appends `total_seconds` as `HH:MM:SS`, without going through
+    /// `core::fmt`.
+    ///
+    /// Hours are not wrapped at 24 and take more than two digits when needed.
+    /// If it doesn't fit nothing is written.
+    pub fn write_hms(&mut self, total_seconds: u32) -> Result<(), CapacityError> {
+        // Room for the ten digits of the largest hours, then `:MM:SS`
+        let mut scratch = *b"0000000000:00:00";
+        let start = render_u32(total_seconds / 3600, &mut scratch[..10]).min(8);
+        let minutes = (total_seconds / 60 % 60) as u8;
+        let seconds = (total_seconds % 60) as u8;
+        scratch[11] = b'0' + minutes / 10;
+        scratch[12] = b'0' + minutes % 10;
+        scratch[14] = b'0' + seconds / 10;
+        scratch[15] = b'0' + seconds % 10;
+        self.push_bytes(&scratch[start..])
+    }
+
+    /// Appends `bytes` encoded as standard base64, with `=` padding.
+    ///
+    /// If the encoded bytes don't fit nothing is written.
+    pub fn write_base64(&mut self, bytes: &[u8]) -> Result<(), CapacityError> {
+        const ALPHABET: &[u8; 64] =
+            b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        let groups = bytes.len() / 3 + usize::from(bytes.len() % 3 != 0);
+        let requested = groups.saturating_mul(4);
+        let new_cursor = self.cursor_after(requested).ok_or(CapacityError {
+            requested,
+            available: self.remaining(),
+        })?;
+        let out = self.buffer[self.cursor..new_cursor].chunks_exact_mut(4);
+        for (quad, chunk) in out.zip(bytes.chunks(3)) {
+            let b = [
+                chunk[0],
+                chunk.get(1).copied().unwrap_or(0),
+                chunk.get(2).copied().unwrap_or(0),
+            ];
+            quad[0] = ALPHABET[usize::from(b[0] >> 2)];
+            quad[1] = ALPHABET[usize::from((b[0] & 0x03) << 4 | b[1] >> 4)];
+            quad[2] = ALPHABET[usize::from((b[1] & 0x0f) << 2 | b[2] >> 6)];
+            quad[3] = ALPHABET[usize::from(b[2] & 0x3f)];
+            for padding in &mut quad[1 + chunk.len()..] {
+                *padding = b'=';
+            }
+        }
+        self.grow_to(new_cursor);
+        Ok(())
+    }
+
+    /// Removes the last character from the buffer and returns it, or `None`
+    /// if the buffer is empty.
+    ///
+    /// # Panics
+    ///
+    /// If the content is not valid UTF-8, see [`Self::as_str`].
+    pub fn pop(&mut self) -> Option<char> {
+        let c = self.as_str().chars().next_back()?;
+        self.cursor -= c.len_utf8();
+        Some(c)
+    }
+
+    /// Writes the content to `out` and then resets the buffer.
+    ///
+    /// If `out` fails, or the content is not valid UTF-8, the buffer is left
+    /// intact, so that the caller can retry.
+    pub fn drain_to<W: fmt::Write>(&mut self, out: &mut W) -> fmt::Result {
+        out.write_str(self.try_as_str().map_err(|_| fmt::Error)?)?;
+        self.reset();
+        Ok(())
+    }
+
+    /// Removes the bytes in `range`, shifting the following content left.
+    ///
+    /// Fails, leaving the buffer unchanged, if `range` is not within the
+    /// written content or one of its endpoints is inside a multi-byte
+    /// character.
+    pub fn remove_range(&mut self, range: Range<usize>) -> Result<(), RangeError> {
+        self.check_range(&range)?;
+        self.buffer.copy_within(range.end..self.cursor, range.start);
+        self.cursor -= range.end - range.start;
+        Ok(())
+    }
+
+    /// Replaces the bytes in `range` with `replacement`, shifting the
+    /// following content as needed.
+    ///
+    /// Fails, leaving the buffer unchanged, if `range` is not valid as for
+    /// [`remove_range`](Self::remove_range) or if the result doesn't fit.
+    pub fn replace_range(
+        &mut self,
+        range: Range<usize>,
+        replacement: &str,
+    ) -> Result<(), ReplaceError> {
+        self.check_range(&range).map_err(ReplaceError::Range)?;
+        let removed = range.end - range.start;
+        let available = self.remaining() + removed;
+        if replacement.len() > available {
+            return Err(ReplaceError::Capacity(CapacityError {
+                requested: replacement.len(),
+                available,
+            }));
+        }
+        let end = range.start + replacement.len();
+        self.buffer.copy_within(range.end..self.cursor, end);
+        self.buffer[range.start..end].copy_from_slice(replacement.as_bytes());
+        self.grow_to(self.cursor - removed + replacement.len());
+        Ok(())
+    }
+
+    /// Checks that `range` is within the written content and both its
+    /// endpoints lie on char boundaries.
+    fn check_range(&self, range: &Range<usize>) -> Result<(), RangeError> {
+        if range.start > range.end {
+            return Err(RangeError::StartAfterEnd {
+                start: range.start,
+                end: range.end,
+            });
+        }
+        if range.end > self.cursor {
+            return Err(RangeError::OutOfBounds {
+                end: range.end,
+                len: self.cursor,
+            });
+        }
+        for &index in &[range.start, range.end] {
+            if !self.is_char_boundary(index) {
+                return Err(RangeError::NotCharBoundary(CharBoundaryError { index }));
+            }
+        }
+        Ok(())
+    }
+
+    /// Inserts `s` at byte index `idx`, shifting the following content right.
+    ///
+    /// Fails, leaving the buffer unchanged, if `idx` is past the written
+    /// content or inside a multi-byte character, or if `s` doesn't fit.
+    pub fn insert_str(&mut self, idx: usize, s: &str) -> Result<(), InsertError> {
+        if idx > self.cursor {
+            return Err(InsertError::OutOfBounds {
+                index: idx,
+                len: self.cursor,
+            });
+        }
+        if !self.is_char_boundary(idx) {
+            return Err(InsertError::NotCharBoundary(CharBoundaryError {
+                index: idx,
+            }));
+        }
+        let new_cursor =
+            self.cursor_after(s.len())
+                .ok_or(InsertError::Capacity(CapacityError {
+                    requested: s.len(),
+                    available: self.remaining(),
+                }))?;
+        self.buffer.copy_within(idx..self.cursor, idx + s.len());
+        self.buffer[idx..idx + s.len()].copy_from_slice(s.as_bytes());
+        self.grow_to(new_cursor);
+        Ok(())
+    }
+
+    /// Inserts `s` at the start, shifting the existing content right.
+    ///
+    /// If `s` doesn't fit the buffer is left unchanged.
+    pub fn prepend_str(&mut self, s: &str) -> Result<(), CapacityError> {
+        self.insert_str(0, s).map_err(|e| match e {
+            InsertError::Capacity(e) => e,
+            // The start is always in bounds and on a char boundary
+            InsertError::OutOfBounds { .. } | InsertError::NotCharBoundary(_) => {
+                unreachable!("inserting at the start can only overflow")
+            }
+        })
+    }
+
+    /// Splits the buffer at byte index `at`, returning a new buffer with the
+    /// content from `at` onward and keeping the content before it.
+    ///
+    /// Fails, leaving the buffer unchanged, if `at` is past the written content
+    /// or inside a multi-byte character.
+    pub fn split_off(&mut self, at: usize) -> Result<WriteBuffer<N>, SplitError> {
+        if at > self.cursor {
+            return Err(SplitError::OutOfBounds {
+                index: at,
+                len: self.cursor,
+            });
+        }
+        if !self.is_char_boundary(at) {
+            return Err(SplitError::NotCharBoundary(CharBoundaryError { index: at }));
+        }
+        let mut tail = WriteBuffer::with_policy(self.policy);
+        tail.buffer[..self.cursor - at].copy_from_slice(&self.buffer[at..self.cursor]);
+        tail.grow_to(self.cursor - at);
+        tail.raw = self.raw;
+        self.cursor = at;
+        Ok(tail)
+    }
+
+    /// Runs `f` over the written bytes, then checks they are still valid
+    /// UTF-8.
+    ///
+    /// This allows arbitrary in-place byte transformations. If `f` leaves
+    /// invalid UTF-8 behind, the error is returned and the content is treated
+    /// from then on as raw bytes, as after [`write_bytes`](Self::write_bytes).
+    pub fn modify_bytes<F: FnOnce(&mut [u8])>(&mut self, f: F) -> Result<(), core::str::Utf8Error> {
+        f(self.as_slice_mut());
+        let result = core::str::from_utf8(self.as_slice()).map(|_| ());
+        self.raw = result.is_err();
+        result
+    }
+
+    /// Removes trailing whitespace from the buffer.
+    ///
+    /// # Panics
+    ///
+    /// If the content is not valid UTF-8, see [`Self::as_str`].
+    pub fn trim_end_in_place(&mut self) {
+        self.cursor = self.as_str().trim_end().len();
+    }
+
+    /// Removes leading whitespace from the buffer.
+    ///
+    /// Unlike [`trim_end_in_place`](Self::trim_end_in_place) this needs to
+    /// shift the remaining content to the start of the buffer.
+    ///
+    /// # Panics
+    ///
+    /// If the content is not valid UTF-8, see [`Self::as_str`].
+    pub fn trim_start_in_place(&mut self) {
+        let start = self.cursor - self.as_str().trim_start().len();
+        self.buffer.copy_within(start..self.cursor, 0);
+        self.cursor -= start;
+    }
+
+    /// Keeps only the last `max_bytes` bytes of the content, shifting them to
+    /// the start, for example for a rolling status line.
+    ///
+    /// If the content is not longer than `max_bytes` this has no effect. The
+    /// tail is never rounded: if it would start inside a multi-byte character
+    /// this fails, leaving the buffer unchanged.
+    pub fn keep_tail(&mut self, max_bytes: usize) -> Result<(), KeepTailError> {
+        let start = match self.cursor.checked_sub(max_bytes) {
+            Some(start) if start > 0 => start,
+            _ => return Ok(()),
+        };
+        if !self.is_char_boundary(start) {
+            return Err(KeepTailError::NotCharBoundary(CharBoundaryError {
+                index: start,
+            }));
+        }
+        self.buffer.copy_within(start..self.cursor, 0);
+        self.cursor = max_bytes;
+        Ok(())
+    }
+
+    /// Retains only the characters for which `f` returns `true`, compacting
+    /// the buffer in place, like `String::retain`.
+    ///
+    /// # Panics
+    ///
+    /// If the content is not valid UTF-8, see [`Self::as_str`].
+    pub fn retain<F: FnMut(char) -> bool>(&mut self, mut f: F) {
+        let len = self.as_str().len();
+        let mut read = 0;
+        let mut write = 0;
+        while read < len {
+            // SAFETY: the bytes from `read` on have not been moved yet, they
+            // were validated above and `read` is on a char boundary
+            let rest = unsafe { core::str::from_utf8_unchecked(&self.buffer[read..len]) };
+            let c = rest.chars().next().expect("`read` is before the end");
+            let width = c.len_utf8();
+            if f(c) {
+                self.buffer.copy_within(read..read + width, write);
+                write += width;
+            }
+            read += width;
+        }
+        self.cursor = write;
+    }
+
+    /// Converts the ASCII letters of the content to upper case in place,
+    /// leaving any other byte untouched.
+    pub fn make_ascii_uppercase(&mut self) {
+        self.as_slice_mut().make_ascii_uppercase();
+    }
+
+    /// Converts the ASCII letters of the content to lower case in place,
+    /// leaving any other byte untouched.
+    pub fn make_ascii_lowercase(&mut self) {
+        self.as_slice_mut().make_ascii_lowercase();
+    }
+}
+
+impl<const N: usize> Default for WriteBuffer<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize, const M: usize> PartialEq<WriteBuffer<M>> for WriteBuffer<N> {
+    fn eq(&self, other: &WriteBuffer<M>) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl<const N: usize> TryFrom<&str> for WriteBuffer<N> {
+    type Error = CapacityError;
+
+    /// Creates a buffer containing `s`, failing if it is longer than `N` bytes.
+    fn try_from(s: &str) -> Result<Self, CapacityError> {
+        let mut buffer = Self::new();
+        buffer.push_str(s)?;
+        Ok(buffer)
+    }
+}
+
+impl<const N: usize> TryFrom<char> for WriteBuffer<N> {
+    type Error = CapacityError;
+
+    /// Creates a buffer containing `c`, failing if its UTF-8 encoding is
+    /// longer than `N` bytes.
+    fn try_from(c: char) -> Result<Self, CapacityError> {
+        let mut buffer = Self::new();
+        buffer.push(c)?;
+        Ok(buffer)
+    }
+}
+
+impl<const N: usize> TryFrom<&[u8]> for WriteBuffer<N> {
+    type Error = FromBytesError;
+
+    /// Creates a buffer containing `bytes`, failing if they are not valid
+    /// UTF-8 or longer than `N`.
+    fn try_from(bytes: &[u8]) -> Result<Self, FromBytesError> {
+        let s = core::str::from_utf8(bytes).map_err(FromBytesError::Utf8)?;
+        Self::try_from(s).map_err(FromBytesError::Capacity)
+    }
+}
+
+impl<const N: usize> core::str::FromStr for WriteBuffer<N> {
+    type Err = CapacityError;
+
+    fn from_str(s: &str) -> Result<Self, CapacityError> {
+        Self::try_from(s)
+    }
+}
+
+impl<const N: usize> PartialEq<str> for WriteBuffer<N> {
+    fn eq(&self, other: &str) -> bool {
+        self.as_slice() == other.as_bytes()
+    }
+}
+
+impl<'a, const N: usize> PartialEq<&'a str> for WriteBuffer<N> {
+    fn eq(&self, other: &&'a str) -> bool {
+        self.as_slice() == other.as_bytes()
+    }
+}
+
+impl<const N: usize> PartialEq<WriteBuffer<N>> for str {
+    fn eq(&self, other: &WriteBuffer<N>) -> bool {
+        self.as_bytes() == other.as_slice()
+    }
+}
+
+impl<const N: usize> PartialEq<WriteBuffer<N>> for &str {
+    fn eq(&self, other: &WriteBuffer<N>) -> bool {
+        self.as_bytes() == other.as_slice()
+    }
+}
+
+/// Hashes the written bytes the same way `str` hashes its content, so that
+/// buffers can be looked up by `&str` through [`Borrow`].
+impl<const N: usize> core::hash::Hash for WriteBuffer<N> {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        state.write(self.as_slice());
+        state.write_u8(0xff);
+    }
+}
+
+impl<const N: usize> Eq for WriteBuffer<N> {}
+
+impl<const N: usize> PartialOrd for WriteBuffer<N> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Orders buffers lexicographically by their written bytes, which for valid
+/// UTF-8 is the same order as their string content.
+impl<const N: usize> Ord for WriteBuffer<N> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_slice().cmp(other.as_slice())
+    }
+}
 
 impl<const N: usize> fmt::Write for WriteBuffer<N> {
     fn write_str(&mut self, s: &str) -> fmt::Result {
+        match self.policy {
+            OverflowPolicy::Fail => self.push_str(s).map_err(|e| {
+                if cfg!(feature = "panic-on-overflow") {
+                    panic!("{}", e);
+                }
+                fmt::Error
+            }),
+            OverflowPolicy::Truncate => {
+                // Once something is dropped later strings are dropped too, so
+                // that the content stays a prefix of what was written
+                if !self.truncated {
+                    let written = self.push_str_truncated(s);
+                    self.truncated = written < s.len();
+                }
+                Ok(())
+            }
+            OverflowPolicy::Wrap => {
+                if s.len() <= self.remaining() {
+                    // Can't fail, `s` fits in the remaining space
+                    let _ = self.push_str(s);
+                } else if s.len() >= N {
+                    self.reset();
+                    let mut start = s.len() - N;
+                    while !s.is_char_boundary(start) {
+                        start += 1;
+                    }
+                    self.push_str_truncated(&s[start..]);
+                    self.truncated = true;
+                } else {
+                    // Shift the most recent content that leaves room for `s`
+                    // to the front, like `keep_tail`
+                    let mut start = self.cursor - (N - s.len());
+                    while !self.is_char_boundary(start) {
+                        start += 1;
+                    }
+                    self.buffer.copy_within(start..self.cursor, 0);
+                    self.cursor -= start;
+                    // Can't fail, room for `s` has just been made
+                    let _ = self.push_str(s);
+                    self.truncated = true;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Writes the formatted arguments, leaving the buffer unchanged if they
+    /// don't fit entirely.
+    ///
+    /// With the [`OverflowPolicy::Wrap`] policy, once older content has been
+    /// discarded the write can't be undone: if a formatting trait
+    /// implementation then fails, the output written so far is kept.
+    fn write_fmt(&mut self, args: fmt::Arguments<'_>) -> fmt::Result {
+        /// Forwards to `write_str`, noting if the `Wrap` policy moved or
+        /// discarded the existing content
+        struct Tracking<'a, const N: usize> {
+            buffer: &'a mut WriteBuffer<N>,
+            wrapped: bool,
+        }
+
+        impl<const N: usize> fmt::Write for Tracking<'_, N> {
+            fn write_str(&mut self, s: &str) -> fmt::Result {
+                self.wrapped |=
+                    self.buffer.policy == OverflowPolicy::Wrap && s.len() > self.buffer.remaining();
+                self.buffer.write_str(s)
+            }
+        }
+
+        let start = self.cursor;
+        let high_water = self.high_water;
+        let truncated = self.truncated;
+        let mut tracking = Tracking {
+            buffer: self,
+            wrapped: false,
+        };
+        let result = fmt::write(&mut tracking, args);
+        // Rolling back the cursor is only sound if the content before it
+        // hasn't moved
+        if result.is_err() && !tracking.wrapped {
+            self.roll_back(start, high_water);
+            self.truncated = truncated;
+        }
+        result
+    }
+}
+
+/// Panics if the content is not valid UTF-8, see [`WriteBuffer::as_str`].
+impl<const N: usize> Deref for WriteBuffer<N> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<const N: usize> DerefMut for WriteBuffer<N> {
+    fn deref_mut(&mut self) -> &mut str {
+        self.as_str_mut()
+    }
+}
+
+/// Appends a string, as in `buffer += "text"`.
+///
+/// # Panics
+///
+/// Panics if the string doesn't fit, use [`WriteBuffer::push_str`] for a
+/// fallible version.
+impl<const N: usize> AddAssign<&str> for WriteBuffer<N> {
+    fn add_assign(&mut self, s: &str) {
+        if let Err(e) = self.push_str(s) {
+            panic!("{}", e);
+        }
+    }
+}
+
+/// Appends a character, as in `buffer += 'c'`.
+///
+/// # Panics
+///
+/// Panics if the character doesn't fit, use [`WriteBuffer::push`] for a
+/// fallible version.
+impl<const N: usize> AddAssign<char> for WriteBuffer<N> {
+    fn add_assign(&mut self, c: char) {
+        if let Err(e) = self.push(c) {
+            panic!("{}", e);
+        }
+    }
+}
+
+/// Appends the characters of an iterator.
+///
+/// # Panics
+///
+/// Panics if a character doesn't fit, since `Extend` can't report errors.
+/// The characters before it are kept. Use [`WriteBuffer::push`] or
+/// [`WriteBuffer::try_from_chars`] for a fallible version.
+impl<const N: usize> Extend<char> for WriteBuffer<N> {
+    fn extend<I: IntoIterator<Item = char>>(&mut self, iter: I) {
+        for c in iter {
+            *self += c;
+        }
+    }
+}
+
+/// Appends the strings of an iterator.
+///
+/// # Panics
+///
+/// Panics if a string doesn't fit, since `Extend` can't report errors.
+/// The strings before it are kept. Use [`WriteBuffer::push_str`] or
+/// [`WriteBuffer::try_from_strs`] for a fallible version.
+impl<'a, const N: usize> Extend<&'a str> for WriteBuffer<N> {
+    fn extend<I: IntoIterator<Item = &'a str>>(&mut self, iter: I) {
+        for s in iter {
+            *self += s;
+        }
+    }
+}
+
+/// Allows looking up buffers by `&str`, for example in a map keyed by
+/// buffers. Consistent with the `Eq`, `Ord` and `Hash` implementations.
+///
+/// Panics if the content is not valid UTF-8, see [`WriteBuffer::as_str`].
+impl<const N: usize> Borrow<str> for WriteBuffer<N> {
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
+/// Panics if the content is not valid UTF-8, see [`WriteBuffer::as_str`].
+impl<const N: usize> AsRef<str> for WriteBuffer<N> {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<const N: usize> AsRef<[u8]> for WriteBuffer<N> {
+    fn as_ref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+/// Shows only the written content, not the whole backing array.
+impl<const N: usize> fmt::Debug for WriteBuffer<N> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("WriteBuffer");
+        debug.field("len", &self.len()).field("cap", &N);
+        match self.try_as_str() {
+            Ok(s) => debug.field("str", &s),
+            Err(_) => debug.field("bytes", &self.as_slice()),
+        };
+        debug.finish()
+    }
+}
+
+/// Fails with `fmt::Error` if the content is not valid UTF-8.
+impl<const N: usize> Display for WriteBuffer<N> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.pad(self.try_as_str().map_err(|_| fmt::Error)?)
+    }
+}
+
+/// Carries the compile time check of [`WriteBuffer::assert_fits`]
+struct AssertFits<const N: usize, const MIN: usize>;
+
+impl<const N: usize, const MIN: usize> AssertFits<N, MIN> {
+    // `core::panic!` rather than `assert!`, so that the error points into
+    // `core` with or without the `std` feature
+    const OK: () = if N < MIN {
+        core::panic!("the buffer capacity is too small");
+    };
+}
+
+/// Only counts the written bytes
+struct Counter(usize);
+
+impl fmt::Write for Counter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0 += s.len();
+        Ok(())
+    }
+}
+
+/// Renders `n` in decimal at the end of `scratch`, returning the index of the
+/// first digit.
+///
+/// Kept separate from [`render_u64`] so that 32-bit targets don't need 64-bit
+/// divisions for the smaller integers.
+fn render_u32(mut n: u32, scratch: &mut [u8]) -> usize {
+    let mut start = scratch.len();
+    loop {
+        start -= 1;
+        scratch[start] = b'0' + (n % 10) as u8;
+        n /= 10;
+        if n == 0 {
+            return start;
+        }
+    }
+}
+
+/// Renders `n` in decimal at the end of `scratch`, returning the index of the
+/// first digit.
+fn render_u64(mut n: u64, scratch: &mut [u8]) -> usize {
+    let mut start = scratch.len();
+    loop {
+        start -= 1;
+        scratch[start] = b'0' + (n % 10) as u8;
+        n /= 10;
+        if n == 0 {
+            return start;
+        }
+    }
+}
+
+/// Returns the JSON escape sequence for `c`, or `None` if it can be written as
+/// is. `scratch` holds the `\u00XX` form of the control characters.
+fn json_escape(c: char, scratch: &mut [u8; 6]) -> Option<&[u8]> {
+    let escape: &[u8] = match c {
+        '"' => b"\\\"",
+        '\\' => b"\\\\",
+        '\n' => b"\\n",
+        '\r' => b"\\r",
+        '\t' => b"\\t",
+        '\u{8}' => b"\\b",
+        '\u{c}' => b"\\f",
+        c if c < ' ' => {
+            let digits = b"0123456789abcdef";
+            *scratch = *b"\\u0000";
+            scratch[4] = digits[c as usize >> 4];
+            scratch[5] = digits[c as usize & 0x0f];
+            scratch
+        }
+        _ => return None,
+    };
+    Some(escape)
+}
+
+/// Calls `f` with the pieces of `bytes` decoded as UTF-8, with U+FFFD in place
+/// of each invalid sequence.
+fn for_each_lossy<F: FnMut(&str)>(mut bytes: &[u8], mut f: F) {
+    loop {
+        match core::str::from_utf8(bytes) {
+            Ok(s) => return f(s),
+            Err(e) => {
+                let (valid, rest) = bytes.split_at(e.valid_up_to());
+                // SAFETY: `from_utf8` validated the bytes up to `valid_up_to`
+                f(unsafe { core::str::from_utf8_unchecked(valid) });
+                f("\u{FFFD}");
+                match e.error_len() {
+                    Some(len) => bytes = &rest[len..],
+                    None => return,
+                }
+            }
+        }
+    }
+}
+
+/// A guard writing into a [`WriteBuffer`], returned by
+/// [`WriteBuffer::transaction`].
+///
+/// Unless [`commit`](Self::commit) is called, the buffer goes back to its
+/// previous length when the guard is dropped. Writes through the guard ignore
+/// the overflow policy: a write that doesn't fit fails and writes nothing.
+///
+/// ```
+/// use core::fmt::Write;
+/// use e_write_buffer::WriteBuffer;
+///
+/// let mut buffer: WriteBuffer<8> = WriteBuffer::new();
+/// buffer.push_str("a").unwrap();
+/// let mut transaction = buffer.transaction();
+/// write!(transaction, "{}", 42).unwrap();
+/// drop(transaction);
+/// assert_eq!(buffer.as_str(), "a");
+/// ```
+pub struct Transaction<'a, const N: usize> {
+    buffer: &'a mut WriteBuffer<N>,
+    checkpoint: Checkpoint,
+}
+
+impl<const N: usize> Transaction<'_, N> {
+    /// Keeps the writes made through the guard.
+    pub fn commit(self) {
+        core::mem::forget(self);
+    }
+}
+
+impl<const N: usize> fmt::Write for Transaction<'_, N> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.buffer.push_str(s).map_err(|_| fmt::Error)
+    }
+}
+
+impl<const N: usize> Drop for Transaction<'_, N> {
+    fn drop(&mut self) {
+        self.buffer.restore(self.checkpoint);
+    }
+}
+
+/// A write buffer backed by a borrowed byte slice
+///
+/// Like [`WriteBuffer`], but the capacity is the length of the slice, known at
+/// runtime. Useful to share a single large static array between subsystems.
+pub struct WriteBufferMut<'a> {
+    buffer: &'a mut [u8],
+    cursor: usize,
+}
+
+impl<'a> WriteBufferMut<'a> {
+    /// Creates a write buffer writing into `buffer`
+    pub fn new(buffer: &'a mut [u8]) -> Self {
+        WriteBufferMut { buffer, cursor: 0 }
+    }
+
+    /// Returns a slice containing the already written bytes in the buffer
+    pub fn as_slice(&self) -> &[u8] {
+        &self.buffer[..self.cursor]
+    }
+
+    /// Reset the buffer such that it can be reused.
+    ///
+    /// Note: This does _not_ overwrite any data in memory, it only sets the
+    /// internal cursor back to the start of the buffer.
+    pub fn reset(&mut self) {
+        self.cursor = 0;
+    }
+
+    /// Converts the buffer into `&str`.
+    pub fn as_str(&self) -> &str {
+        // SAFETY: Only whole `str`s are ever written into `self.buffer`.
+        // Therefore it is always guaranteed that the buffer contains valid
+        // UTF-8.
+        unsafe { core::str::from_utf8_unchecked(self.as_slice()) }
+    }
+
+    /// Returns the total number of bytes the buffer can hold, that is the
+    /// length of the borrowed slice.
+    pub fn capacity(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Returns how many bytes in the buffer have already been written.
+    pub fn len(&self) -> usize {
+        self.cursor
+    }
+
+    /// Returns true if zero bytes in the buffer are written.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns how many bytes in the buffer remain for writing.
+    pub fn remaining(&self) -> usize {
+        self.capacity() - self.len()
+    }
+
+    /// Returns true if the buffer is full.
+    pub fn is_full(&self) -> bool {
+        self.remaining() == 0
+    }
+
+    /// Appends `s` to the buffer.
+    ///
+    /// If `s` does not fit in the remaining space nothing is written.
+    pub fn push_str(&mut self, s: &str) -> Result<(), CapacityError> {
         let bytes = s.as_bytes();
+        if bytes.len() > self.remaining() {
+            return Err(CapacityError {
+                requested: bytes.len(),
+                available: self.remaining(),
+            });
+        }
+        let new_cursor = self.cursor + bytes.len();
+        self.buffer[self.cursor..new_cursor].copy_from_slice(bytes);
+        self.cursor = new_cursor;
+        Ok(())
+    }
+}
+
+impl fmt::Write for WriteBufferMut<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push_str(s).map_err(|_| fmt::Error)
+    }
+}
+
+/// Shows only the written content, not the whole borrowed slice.
+impl fmt::Debug for WriteBufferMut<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WriteBufferMut")
+            .field("len", &self.len())
+            .field("cap", &self.capacity())
+            .field("str", &self.as_str())
+            .finish()
+    }
+}
+
+impl Display for WriteBufferMut<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.pad(self.as_str())
+    }
+}
+
+/// Serializes the content as a string, failing if it is not valid UTF-8.
+#[cfg(feature = "serde")]
+impl<const N: usize> serde::Serialize for WriteBuffer<N> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::Error as _;
+        let s = self.try_as_str().map_err(S::Error::custom)?;
+        serializer.serialize_str(s)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, const N: usize> serde::Deserialize<'de> for WriteBuffer<N> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Visitor<const N: usize>;
+
+        impl<'de, const N: usize> serde::de::Visitor<'de> for Visitor<N> {
+            type Value = WriteBuffer<N>;
+
+            fn expecting(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                write!(f, "a string of at most {} bytes", N)
+            }
+
+            fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                let mut buffer = WriteBuffer::new();
+                buffer
+                    .push_str(v)
+                    .map_err(|_| E::invalid_length(v.len(), &self))?;
+                Ok(buffer)
+            }
+        }
+
+        deserializer.deserialize_str(Visitor)
+    }
+}
+
+/// Formats the written content as a string, so a buffer can be passed
+/// straight to the `defmt` logging macros. Content that is not valid UTF-8
+/// is formatted as bytes.
+///
+/// ```no_run
+/// use core::fmt::Write as _;
+/// use e_write_buffer::WriteBuffer;
+///
+/// let mut buffer: WriteBuffer<20> = WriteBuffer::new();
+/// write!(buffer, "{}", 12).unwrap();
+/// defmt::info!("value: {}", buffer);
+/// ```
+#[cfg(feature = "defmt")]
+impl<const N: usize> defmt::Format for WriteBuffer<N> {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        match self.try_as_str() {
+            Ok(s) => defmt::write!(f, "{=str}", s),
+            Err(_) => defmt::write!(f, "{=[u8]}", self.as_slice()),
+        }
+    }
+}
+
+#[cfg(feature = "ufmt")]
+impl<const N: usize> ufmt::uWrite for WriteBuffer<N> {
+    type Error = CapacityError;
+
+    fn write_str(&mut self, s: &str) -> Result<(), CapacityError> {
+        self.push_str(s)
+    }
+}
+
+#[cfg(feature = "embedded-io")]
+impl embedded_io::Error for CapacityError {
+    fn kind(&self) -> embedded_io::ErrorKind {
+        embedded_io::ErrorKind::WriteZero
+    }
+}
+
+#[cfg(feature = "embedded-io")]
+impl<const N: usize> embedded_io::ErrorType for WriteBuffer<N> {
+    type Error = CapacityError;
+}
+
+/// Writes raw bytes into the buffer.
+///
+/// The bytes are not required to be valid UTF-8: once written through this
+/// trait the content is validated by [`WriteBuffer::as_str`], which panics if
+/// it isn't valid UTF-8.
+#[cfg(feature = "embedded-io")]
+impl<const N: usize> embedded_io::Write for WriteBuffer<N> {
+    /// Copies as many bytes as fit, failing only if the buffer is full.
+    fn write(&mut self, buf: &[u8]) -> Result<usize, CapacityError> {
+        if !buf.is_empty() && self.is_full() {
+            return Err(CapacityError {
+                requested: buf.len(),
+                available: 0,
+            });
+        }
+        Ok(self.push_raw_truncated(buf))
+    }
+
+    fn flush(&mut self) -> Result<(), CapacityError> {
+        Ok(())
+    }
+}
+
+/// Writes raw bytes into the buffer.
+///
+/// The bytes are not required to be valid UTF-8: once written through this
+/// trait the content is validated by [`WriteBuffer::as_str`], which panics if
+/// it isn't valid UTF-8.
+#[cfg(feature = "std")]
+impl<const N: usize> std::io::Write for WriteBuffer<N> {
+    /// Copies as many bytes as fit, returning `Ok(0)` if the buffer is full.
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        Ok(self.push_raw_truncated(buf))
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Zeroes the whole backing array, including the bytes past the cursor that
+/// are left over by [`WriteBuffer::reset`], and resets the buffer.
+///
+/// The buffer is `Copy` and doesn't wipe itself on drop, wrap it in
+/// `zeroize::Zeroizing` for that. Copies made along the way are not wiped.
+///
+/// ```
+/// use core::fmt::Write as _;
+/// use e_write_buffer::WriteBuffer;
+/// use zeroize::Zeroizing;
+///
+/// let mut secret = Zeroizing::new(WriteBuffer::<16>::new());
+/// write!(secret, "pw: {}", 1234).unwrap();
+/// assert_eq!(*secret, "pw: 1234");
+/// // wiped here, when `secret` goes out of scope
+/// ```
+#[cfg(feature = "zeroize")]
+impl<const N: usize> zeroize::Zeroize for WriteBuffer<N> {
+    fn zeroize(&mut self) {
+        self.buffer.zeroize();
+        self.reset();
+    }
+}
+
+#[cfg(feature = "heapless")]
+impl<const N: usize> WriteBuffer<N> {
+    /// Copies the content into a `heapless::String`, failing if it doesn't
+    /// fit in `M` bytes.
+    ///
+    /// # Panics
+    ///
+    /// If the content is not valid UTF-8, see [`Self::as_str`].
+    pub fn to_heapless_string<const M: usize>(&self) -> Result<heapless::String<M>, CapacityError> {
+        let mut string = heapless::String::new();
+        string.push_str(self.as_str()).map_err(|()| CapacityError {
+            requested: self.len(),
+            available: M,
+        })?;
+        Ok(string)
+    }
+}
+
+#[cfg(feature = "heapless")]
+impl<const N: usize, const M: usize> TryFrom<heapless::String<M>> for WriteBuffer<N> {
+    type Error = CapacityError;
+
+    /// Creates a buffer with the content of `string`, failing if it is longer
+    /// than `N`.
+    fn try_from(string: heapless::String<M>) -> Result<Self, CapacityError> {
+        Self::try_from(string.as_str())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        Align, CStrError, CapacityError, CharBoundaryError, FromArrayError, FromBytesError,
+        InsertError, KeepTailError, OverflowPolicy, PadError, RangeError, ReplaceError,
+        SetCursorError, SplitError, Transaction, WriteBuffer, WriteBufferMut,
+    };
+    use core::convert::TryFrom;
+    use core::fmt::Write;
+
+    const _: WriteBuffer<4> = WriteBuffer::new();
+    static EMPTY: WriteBuffer<4> = WriteBuffer::new();
+
+    // Overflowing `write!`s panic with the `panic-on-overflow` feature
+    #[cfg(not(feature = "panic-on-overflow"))]
+    #[test]
+    #[allow(clippy::write_literal)]
+    fn test_write_wrapper() {
+        let x = 123;
+
+        let mut buffer: WriteBuffer<20> = WriteBuffer::new();
+        write!(buffer, "{}", x).unwrap();
+        assert_eq!(&buffer.as_slice()[0..3], b"123");
+
+        buffer.reset();
+        let x = 2.242424;
+        write!(buffer, "{:.2}", x).unwrap();
+        assert_eq!(buffer.as_slice(), b"2.24");
+
+        buffer.reset();
+        let x = 20;
+        write!(buffer, "Longer than {} characters sentence", x).unwrap_err();
+
+        buffer.reset();
+        write!(buffer, "{}", "1").unwrap();
+        write!(buffer, "{}", "2").unwrap();
+        assert_eq!(buffer.as_slice(), b"12");
+    }
+
+    #[test]
+    fn test_display() {
+        let x = 123;
+
+        let mut buffer: WriteBuffer<20> = WriteBuffer::new();
+        write!(buffer, "{}", x).unwrap();
+        assert_eq!("123", format!("{}", buffer));
+    }
+
+    #[test]
+    fn test_as_str_mut() {
+        let mut buffer: WriteBuffer<20> = WriteBuffer::new();
+        write!(buffer, "hello world").unwrap();
+        buffer.as_str_mut().make_ascii_uppercase();
+
+        assert_eq!(buffer.as_str(), "HELLO WORLD");
+    }
+
+    // Overflowing `write!`s panic with the `panic-on-overflow` feature
+    #[cfg(not(feature = "panic-on-overflow"))]
+    #[test]
+    fn test_is_empty_is_full_and_overflow() {
+        let mut buffer: WriteBuffer<10> = WriteBuffer::new();
+        assert!(buffer.is_empty());
+        write!(buffer, "0123456789").unwrap();
+        assert!(buffer.is_full());
+        assert_eq!(write!(buffer, "!"), Err(core::fmt::Error));
+    }
+
+    #[test]
+    fn test_push_str() {
+        let mut buffer: WriteBuffer<10> = WriteBuffer::new();
+        buffer.push_str("01234567").unwrap();
+        assert_eq!(buffer.as_str(), "01234567");
+
+        let err = buffer.push_str("abc").unwrap_err();
+        assert_eq!(
+            err,
+            CapacityError {
+                requested: 3,
+                available: 2,
+            }
+        );
+        assert_eq!(buffer.as_str(), "01234567");
+
+        buffer.push_str("ab").unwrap();
+        assert!(buffer.is_full());
+    }
+
+    #[test]
+    fn test_push_str_truncated() {
+        let mut buffer: WriteBuffer<10> = WriteBuffer::new();
+        assert_eq!(buffer.push_str_truncated("012345"), 6);
+        assert_eq!(buffer.push_str_truncated("héllo"), 4);
+        assert_eq!(buffer.as_str(), "012345hél");
+
+        // "é" takes two bytes, only its first byte would fit
+        buffer.reset();
+        buffer.push_str("01234567").unwrap();
+        assert_eq!(buffer.push_str_truncated("héllo"), 1);
+        assert_eq!(buffer.as_str(), "01234567h");
+        assert_eq!(buffer.push_str_truncated("abc"), 1);
+        assert!(buffer.is_full());
+        assert_eq!(buffer.push_str_truncated("abc"), 0);
+    }
+
+    #[test]
+    fn test_truncate() {
+        let mut buffer: WriteBuffer<20> = WriteBuffer::new();
+        buffer.push_str("ab€cd").unwrap();
+
+        buffer.truncate(10);
+        assert_eq!(buffer.as_str(), "ab€cd");
+
+        // "€" takes three bytes, from index 2 to 5
+        assert_eq!(buffer.try_truncate(3), Err(CharBoundaryError { index: 3 }));
+        assert_eq!(buffer.try_truncate(4), Err(CharBoundaryError { index: 4 }));
+        assert_eq!(buffer.as_str(), "ab€cd");
+
+        buffer.truncate(5);
+        assert_eq!(buffer.as_str(), "ab€");
+        buffer.truncate(2);
+        assert_eq!(buffer.as_str(), "ab");
+    }
+
+    #[test]
+    #[should_panic(expected = "byte index 3 is not a char boundary")]
+    fn test_truncate_panics_inside_char() {
+        let mut buffer: WriteBuffer<20> = WriteBuffer::new();
+        buffer.push_str("ab€cd").unwrap();
+        buffer.truncate(3);
+    }
+
+    #[test]
+    fn test_deref() {
+        fn takes_str(s: &str) -> usize {
+            s.len()
+        }
+
+        let mut buffer: WriteBuffer<20> = WriteBuffer::new();
+        write!(buffer, " a,b ").unwrap();
+        assert_eq!(takes_str(&buffer), 5);
+        assert_eq!(buffer.trim(), "a,b");
+        assert_eq!(buffer.trim().split(',').count(), 2);
+
+        buffer.make_ascii_uppercase();
+        assert_eq!(buffer.as_str(), " A,B ");
+    }
+
+    #[test]
+    fn test_eq_str() {
+        let mut buffer: WriteBuffer<20> = WriteBuffer::new();
+        write!(buffer, "hello").unwrap();
+
+        assert_eq!(buffer, "hello");
+        assert_eq!("hello", buffer);
+        assert_eq!(buffer, *"hello");
+        assert_eq!(*"hello", buffer);
+        assert_ne!(buffer, "hello world");
+        assert_ne!("hell", buffer);
+        assert_ne!(buffer, "");
+    }
+
+    #[test]
+    fn test_eq_different_capacity() {
+        let mut small: WriteBuffer<10> = WriteBuffer::new();
+        let mut big: WriteBuffer<32> = WriteBuffer::new();
+        write!(small, "abc").unwrap();
+        write!(big, "abc").unwrap();
+        assert_eq!(small, big);
+        assert_eq!(big, small);
+
+        write!(big, "d").unwrap();
+        assert_ne!(small, big);
+
+        big.truncate(3);
+        assert_eq!(small, big);
+    }
+
+    #[test]
+    fn test_hash_like_str() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        fn hash<T: Hash + ?Sized>(t: &T) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            t.hash(&mut hasher);
+            hasher.finish()
+        }
+
+        let mut buffer: WriteBuffer<20> = WriteBuffer::new();
+        write!(buffer, "foo").unwrap();
+        assert_eq!(hash(&buffer), hash("foo"));
+        assert_ne!(hash(&buffer), hash("bar"));
+    }
+
+    #[test]
+    fn test_const() {
+        const BUFFER: WriteBuffer<4> = WriteBuffer::new();
+        const LEN: usize = BUFFER.len();
+        const REMAINING: usize = BUFFER.remaining();
+        assert_eq!(LEN, 0);
+        assert_eq!(REMAINING, 4);
+        assert!(EMPTY.is_empty());
+        assert!(!EMPTY.is_full());
+    }
+
+    #[test]
+    fn test_capacity() {
+        assert_eq!(WriteBuffer::<16>::new().capacity(), 16);
+
+        let mut buffer: WriteBuffer<16> = WriteBuffer::new();
+        write!(buffer, "abc").unwrap();
+        assert_eq!(buffer.capacity(), buffer.len() + buffer.remaining());
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut buffer: WriteBuffer<8> = WriteBuffer::new();
+        write!(buffer, "secret").unwrap();
+        buffer.reset();
+        assert_eq!(&buffer.debug_raw()[..6], b"secret");
+        write!(buffer, "pub").unwrap();
+        assert_eq!(&buffer.debug_raw()[..6], b"pubret");
+        buffer.reset();
+
+        write!(buffer, "secret").unwrap();
+        buffer.clear();
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.debug_raw(), &[0u8; 8]);
+    }
+
+    #[test]
+    #[allow(clippy::clone_on_copy)]
+    fn test_clone() {
+        let mut buffer: WriteBuffer<20> = WriteBuffer::new();
+        write!(buffer, "stable").unwrap();
+
+        let mut attempt = buffer.clone();
+        write!(attempt, " experimental").unwrap();
+        assert_eq!(attempt, "stable experimental");
+        assert_eq!(buffer, "stable");
+
+        buffer = attempt.clone();
+        assert_eq!(buffer, "stable experimental");
+    }
+
+    #[test]
+    fn test_copy() {
+        fn consume(buffer: WriteBuffer<8>) -> usize {
+            buffer.len()
+        }
+
+        let mut buffer: WriteBuffer<8> = WriteBuffer::new();
+        write!(buffer, "abc").unwrap();
+        assert_eq!(consume(buffer), 3);
+
+        // The original is still usable after being passed by value
+        write!(buffer, "d").unwrap();
+        assert_eq!(buffer, "abcd");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_roundtrip() {
+        let mut buffer: WriteBuffer<8> = WriteBuffer::new();
+        write!(buffer, "a\"b").unwrap();
+
+        let json = serde_json::to_string(&buffer).unwrap();
+        assert_eq!(json, r#""a\"b""#);
+        let back: WriteBuffer<8> = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, buffer);
+
+        let err = serde_json::from_str::<WriteBuffer<4>>(r#""too long""#).unwrap_err();
+        assert!(err.to_string().contains("at most 4 bytes"));
+    }
+
+    #[cfg(feature = "ufmt")]
+    #[test]
+    fn test_ufmt() {
+        let mut buffer: WriteBuffer<6> = WriteBuffer::new();
+        ufmt::uwrite!(buffer, "{}-{}", 12u8, 34u8).unwrap();
+        assert_eq!(buffer, "12-34");
+
+        let err = ufmt::uwrite!(buffer, "{}", 56u8).unwrap_err();
+        assert_eq!(
+            err,
+            CapacityError {
+                requested: 2,
+                available: 1,
+            }
+        );
+        ufmt::uwrite!(buffer, "{}", 7u8).unwrap();
+        assert!(buffer.is_full());
+    }
+
+    #[cfg(feature = "embedded-io")]
+    #[test]
+    fn test_embedded_io() {
+        use embedded_io::Write as _;
+
+        let mut buffer: WriteBuffer<4> = WriteBuffer::new();
+        assert_eq!(buffer.write(b"abc").unwrap(), 3);
+        assert_eq!(buffer.write(b"def").unwrap(), 1);
+        assert_eq!(buffer, "abcd");
+        assert_eq!(buffer.write(b"").unwrap(), 0);
+        assert_eq!(
+            buffer.write(b"g"),
+            Err(CapacityError {
+                requested: 1,
+                available: 0,
+            })
+        );
+        buffer.flush().unwrap();
+
+        buffer.reset();
+        buffer.write_all(b"ab").unwrap();
+        assert!(buffer.write_all(b"cde").is_err());
+        assert_eq!(buffer.as_slice(), b"abcd");
+    }
+
+    #[cfg(feature = "embedded-io")]
+    #[test]
+    #[should_panic(expected = "buffer contains invalid UTF-8")]
+    fn test_embedded_io_invalid_utf8() {
+        use embedded_io::Write as _;
+
+        let mut buffer: WriteBuffer<4> = WriteBuffer::new();
+        buffer.write_all(&[0xff]).unwrap();
+        let _ = buffer.as_str();
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_std_io() {
+        use std::io::Write as _;
+
+        let mut buffer: WriteBuffer<4> = WriteBuffer::new();
+        buffer.write_all(b"abc").unwrap();
+        assert_eq!(buffer.write(b"def").unwrap(), 1);
+        assert_eq!(buffer.write(b"g").unwrap(), 0);
+        buffer.flush().unwrap();
+        assert_eq!(buffer, "abcd");
+
+        buffer.reset();
+        let err = buffer.write_all(b"abcde").unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::WriteZero);
+        assert_eq!(buffer, "abcd");
+    }
+
+    #[test]
+    fn test_push() {
+        let mut buffer: WriteBuffer<5> = WriteBuffer::new();
+        buffer.push('a').unwrap();
+        buffer.push('🦀').unwrap();
+        assert_eq!(buffer, "a🦀");
+        assert!(buffer.is_full());
+        assert_eq!(
+            buffer.push('b'),
+            Err(CapacityError {
+                requested: 1,
+                available: 0,
+            })
+        );
+
+        let mut buffer: WriteBuffer<4> = WriteBuffer::new();
+        buffer.push('a').unwrap();
+        assert_eq!(
+            buffer.push('🦀'),
+            Err(CapacityError {
+                requested: 4,
+                available: 3,
+            })
+        );
+        assert_eq!(buffer, "a");
+    }
+
+    #[test]
+    fn test_pop() {
+        let mut buffer: WriteBuffer<20> = WriteBuffer::new();
+        write!(buffer, "aé🦀").unwrap();
+        assert_eq!(buffer.pop(), Some('🦀'));
+        assert_eq!(buffer, "aé");
+        assert_eq!(buffer.pop(), Some('é'));
+        assert_eq!(buffer, "a");
+        assert_eq!(buffer.pop(), Some('a'));
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.pop(), None);
+    }
+
+    #[test]
+    fn test_write_all_str() {
+        let mut buffer: WriteBuffer<8> = WriteBuffer::new();
+        buffer.write_all_str(["ab", "cd"]).unwrap();
+        assert_eq!(buffer, "abcd");
+
+        buffer.reset();
+        let err = buffer
+            .write_all_str(["abc", "def", "ghi", "j"])
+            .unwrap_err();
+        assert_eq!(
+            err,
+            CapacityError {
+                requested: 9,
+                available: 8,
+            }
+        );
+        assert!(buffer.is_empty());
+
+        buffer.push_str("x").unwrap();
+        buffer.write_all_str(["abc", "def", "ghi"]).unwrap_err();
+        assert_eq!(buffer, "x");
+
+        // Stops at the first fragment that doesn't fit
+        let err = buffer.write_all_str(core::iter::repeat("ab")).unwrap_err();
+        assert_eq!(
+            err,
+            CapacityError {
+                requested: 8,
+                available: 7,
+            }
+        );
+        assert_eq!(buffer, "x");
+    }
+
+    // Overflowing `write!`s panic with the `panic-on-overflow` feature
+    #[cfg(not(feature = "panic-on-overflow"))]
+    #[test]
+    fn test_write_fmt_rollback() {
+        let mut buffer: WriteBuffer<6> = WriteBuffer::new();
+        let (ok, too_long) = ("ok", "toolong");
+        write!(buffer, "{}{}", ok, too_long).unwrap_err();
+        assert!(buffer.is_empty());
+
+        write!(buffer, "{}", 1).unwrap();
+        write!(buffer, "{}-{}", 23, 456).unwrap_err();
+        assert_eq!(buffer, "1");
+    }
+
+    #[test]
+    fn test_into_inner() {
+        let mut buffer: WriteBuffer<8> = WriteBuffer::new();
+        write!(buffer, "hi").unwrap();
+        let (array, len) = buffer.into_inner();
+        assert_eq!(len, 2);
+        assert_eq!(&array[..len], b"hi");
+    }
+
+    #[test]
+    fn test_try_from_str() {
+        let buffer = WriteBuffer::<8>::try_from("initial").unwrap();
+        assert_eq!(buffer, "initial");
+        assert!(WriteBuffer::<8>::try_from("").unwrap().is_empty());
+        assert!(WriteBuffer::<8>::try_from("01234567").unwrap().is_full());
+        assert_eq!(
+            WriteBuffer::<8>::try_from("012345678"),
+            Err(CapacityError {
+                requested: 9,
+                available: 8,
+            })
+        );
+
+        let parsed: WriteBuffer<8> = "parsed".parse().unwrap();
+        assert_eq!(parsed, "parsed");
+    }
+
+    #[test]
+    fn test_try_from_bytes() {
+        let buffer = WriteBuffer::<8>::try_from("héllo".as_bytes()).unwrap();
+        assert_eq!(buffer, "héllo");
+
+        let err = WriteBuffer::<8>::try_from(&b"h\xc3llo"[..]).unwrap_err();
+        assert!(matches!(err, FromBytesError::Utf8(_)));
+
+        let err = WriteBuffer::<4>::try_from(&b"hello"[..]).unwrap_err();
+        assert_eq!(
+            err,
+            FromBytesError::Capacity(CapacityError {
+                requested: 5,
+                available: 4,
+            })
+        );
+    }
+
+    #[test]
+    fn test_as_ref() {
+        fn str_len<S: AsRef<str> + ?Sized>(s: &S) -> usize {
+            s.as_ref().chars().count()
+        }
+        fn bytes_len<B: AsRef<[u8]> + ?Sized>(b: &B) -> usize {
+            b.as_ref().len()
+        }
+
+        let buffer = WriteBuffer::<8>::try_from("héllo").unwrap();
+        assert_eq!(str_len(&buffer), 5);
+        assert_eq!(bytes_len(&buffer), 6);
+    }
+
+    #[test]
+    fn test_starts_ends_with_contains() {
+        let buffer = WriteBuffer::<20>::try_from("GET /index").unwrap();
+        assert!(buffer.starts_with("GET "));
+        assert!(!buffer.starts_with("POST "));
+        assert!(buffer.ends_with("index"));
+        assert!(!buffer.ends_with("GET"));
+        assert!(buffer.contains(" /"));
+        assert!(!buffer.contains("html"));
+    }
+
+    // Overflowing `write!`s panic with the `panic-on-overflow` feature
+    #[cfg(not(feature = "panic-on-overflow"))]
+    #[test]
+    fn test_overflow_policy() {
+        let mut buffer: WriteBuffer<4> = WriteBuffer::new();
+        assert_eq!(buffer.policy(), OverflowPolicy::Fail);
+        write!(buffer, "abc").unwrap();
+        write!(buffer, "dé").unwrap_err();
+        assert_eq!(buffer, "abc");
+
+        let mut buffer: WriteBuffer<4> = WriteBuffer::with_policy(OverflowPolicy::Truncate);
+        write!(buffer, "abc").unwrap();
+        write!(buffer, "é").unwrap();
+        assert_eq!(buffer, "abc");
+        // Nothing after the dropped `é`, even if it would fit
+        write!(buffer, "de").unwrap();
+        assert_eq!(buffer, "abc");
+
+        buffer.reset();
+        buffer.set_policy(OverflowPolicy::Wrap);
+        write!(buffer, "abc").unwrap();
+        write!(buffer, "d").unwrap();
+        assert_eq!(buffer, "abcd");
+        write!(buffer, "ef").unwrap();
+        assert_eq!(buffer, "cdef");
+        write!(buffer, "0123456").unwrap();
+        assert_eq!(buffer, "3456");
+        // Only whole chars of the tail are kept
+        write!(buffer, "0123é").unwrap();
+        assert_eq!(buffer, "23é");
+        write!(buffer, "x").unwrap();
+        assert_eq!(buffer, "3éx");
+        write!(buffer, "aé€").unwrap();
+        assert_eq!(buffer, "€");
+        // Keeping the last 2 bytes would split the `€`
+        write!(buffer, "xy").unwrap();
+        assert_eq!(buffer, "xy");
+    }
+
+    #[test]
+    fn test_bytes_and_chars() {
+        let buffer = WriteBuffer::<20>::try_from("aé🦀").unwrap();
+        assert_eq!(buffer.bytes().count(), 7);
+        assert_eq!(
+            buffer.bytes().fold(0u8, |acc, b| acc.wrapping_add(b)),
+            buffer
+                .as_slice()
+                .iter()
+                .fold(0u8, |acc, b| acc.wrapping_add(*b))
+        );
+        assert_eq!(buffer.chars().count(), 3);
+        assert_eq!(buffer.chars().last(), Some('🦀'));
+    }
+
+    #[test]
+    fn test_remaining_mut_and_advance() {
+        let mut buffer = WriteBuffer::<8>::try_from("rx:").unwrap();
+        let tail = buffer.remaining_mut();
+        assert_eq!(tail.len(), 5);
+        tail[..3].copy_from_slice(b"abc");
+        assert_eq!(buffer, "rx:");
+
+        // SAFETY: the 3 bytes just filled are ASCII
+        unsafe { buffer.advance(3) };
+        assert_eq!(buffer, "rx:abc");
+        assert_eq!(buffer.remaining(), 2);
+    }
+
+    #[test]
+    fn test_write_bytes() {
+        let mut buffer: WriteBuffer<8> = WriteBuffer::new();
+        buffer.push_str("ab").unwrap();
+        assert_eq!(buffer.try_as_str(), Ok("ab"));
+
+        buffer.write_bytes("é".as_bytes()).unwrap();
+        assert_eq!(buffer.try_as_str(), Ok("abé"));
+        assert_eq!(buffer, "abé");
+
+        buffer.write_bytes(&[0xc3]).unwrap();
+        let err = buffer.try_as_str().unwrap_err();
+        assert_eq!(err.valid_up_to(), 4);
+        assert_eq!(buffer.as_slice(), b"ab\xc3\xa9\xc3");
+
+        assert_eq!(
+            buffer.write_bytes(&[0; 4]),
+            Err(CapacityError {
+                requested: 4,
+                available: 3,
+            })
+        );
+
+        buffer.reset();
+        buffer.push_str("ok").unwrap();
+        assert_eq!(buffer.try_as_str(), Ok("ok"));
+    }
+
+    #[test]
+    #[should_panic(expected = "buffer contains invalid UTF-8")]
+    fn test_as_str_panics_on_invalid_bytes() {
+        let mut buffer: WriteBuffer<8> = WriteBuffer::new();
+        buffer.write_bytes(&[0xff]).unwrap();
+        let _ = buffer.as_str();
+    }
+
+    #[test]
+    fn test_write_buffer_mut() {
+        let mut storage = [0u8; 8];
+        let mut buffer = WriteBufferMut::new(&mut storage[..6]);
+        assert_eq!(buffer.capacity(), 6);
+        assert!(buffer.is_empty());
+
+        write!(buffer, "{}", 1234).unwrap();
+        assert_eq!(buffer.as_str(), "1234");
+        assert_eq!(buffer.remaining(), 2);
+        write!(buffer, "567").unwrap_err();
+        assert_eq!(
+            buffer.push_str("567"),
+            Err(CapacityError {
+                requested: 3,
+                available: 2,
+            })
+        );
+        write!(buffer, "56").unwrap();
+        assert!(buffer.is_full());
+        assert_eq!(format!("{}", buffer), "123456");
+
+        buffer.reset();
+        assert!(buffer.is_empty());
+        assert_eq!(&storage[..6], b"123456");
+    }
+
+    #[test]
+    fn test_resize_into() {
+        let buffer = WriteBuffer::<8>::try_from("abcdef").unwrap();
+
+        let bigger: WriteBuffer<32> = buffer.resize_into().unwrap();
+        assert_eq!(bigger, "abcdef");
+        assert_eq!(bigger.capacity(), 32);
+
+        let same = buffer.resize_into::<8>().unwrap();
+        assert_eq!(same, buffer);
+
+        assert_eq!(
+            buffer.resize_into::<4>(),
+            Err(CapacityError {
+                requested: 6,
+                available: 4,
+            })
+        );
+    }
+
+    #[test]
+    fn test_debug() {
+        let mut buffer: WriteBuffer<20> = WriteBuffer::new();
+        write!(buffer, "abc").unwrap();
+        let debug = format!("{:?}", buffer);
+        assert_eq!(debug, r#"WriteBuffer { len: 3, cap: 20, str: "abc" }"#);
+        assert!(!debug.contains("0, 0"));
+
+        buffer.write_bytes(&[0xff]).unwrap();
+        assert_eq!(
+            format!("{:?}", buffer),
+            "WriteBuffer { len: 4, cap: 20, bytes: [97, 98, 99, 255] }"
+        );
+
+        let mut storage = [0u8; 8];
+        let mut buffer = WriteBufferMut::new(&mut storage);
+        write!(buffer, "abc").unwrap();
+        assert_eq!(
+            format!("{:?}", buffer),
+            r#"WriteBufferMut { len: 3, cap: 8, str: "abc" }"#
+        );
+    }
+
+    #[test]
+    fn test_cursor_after_does_not_wrap() {
+        let mut buffer: WriteBuffer<8> = WriteBuffer::new();
+        write!(buffer, "a").unwrap();
+        assert_eq!(buffer.cursor_after(7), Some(8));
+        assert_eq!(buffer.cursor_after(8), None);
+        // A wrapping addition would give 0, which is within capacity
+        assert_eq!(buffer.cursor_after(usize::MAX), None);
+    }
+
+    #[test]
+    fn test_checkpoint_restore() {
+        let mut buffer: WriteBuffer<20> = WriteBuffer::new();
+        write!(buffer, "line:").unwrap();
+        let checkpoint = buffer.checkpoint();
+        write!(buffer, " token").unwrap();
+        assert_eq!(buffer, "line: token");
+        buffer.restore(checkpoint);
+        assert_eq!(buffer, "line:");
+
+        // Restoring never moves the cursor forward
+        buffer.truncate(2);
+        buffer.restore(checkpoint);
+        assert_eq!(buffer, "li");
+    }
+
+    #[test]
+    fn test_display_flags() {
+        let buffer = WriteBuffer::<20>::try_from("abc").unwrap();
+        assert_eq!(format!("{}", buffer), "abc");
+        assert_eq!(format!("{:>6}", buffer), "   abc");
+        assert_eq!(format!("{:*<6}", buffer), "abc***");
+        assert_eq!(format!("{:^7}", buffer), "  abc  ");
+        assert_eq!(format!("{:.2}", buffer), "ab");
+        assert_eq!(format!("{:>4.2}", buffer), "  ab");
+    }
+
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn test_zeroize_on_drop() {
+        use core::mem::MaybeUninit;
+        use zeroize::Zeroizing;
+
+        let mut buffer: WriteBuffer<8> = WriteBuffer::new();
+        write!(buffer, "secret").unwrap();
+        buffer.reset();
+        write!(buffer, "pw").unwrap();
+
+        let mut slot = MaybeUninit::new(Zeroizing::new(buffer));
+        let ptr = slot.as_mut_ptr();
+        // SAFETY: `ptr` points to the initialized `slot`, which is dropped
+        // only once. The backing array is plain bytes that stay in `slot`
+        // after the drop, they are read through `ptr` itself so that the drop
+        // doesn't invalidate the pointer
+        let bytes = unsafe {
+            let offset = (*ptr).debug_raw().as_ptr() as usize - ptr as usize;
+            core::ptr::drop_in_place(ptr);
+            core::slice::from_raw_parts(ptr.cast::<u8>().add(offset), 8)
+        };
+        assert_eq!(bytes, &[0u8; 8]);
+    }
+
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn test_zeroize() {
+        use zeroize::Zeroize;
+
+        let mut buffer: WriteBuffer<8> = WriteBuffer::new();
+        write!(buffer, "secret").unwrap();
+        buffer.zeroize();
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.debug_raw(), &[0u8; 8]);
+    }
+
+    #[test]
+    fn test_fill_remaining() {
+        let mut buffer = WriteBuffer::<8>::try_from("ab").unwrap();
+        buffer.fill_remaining(' ').unwrap();
+        assert_eq!(buffer, "ab      ");
+        buffer.fill_remaining(' ').unwrap();
+        assert_eq!(buffer, "ab      ");
+
+        let mut buffer = WriteBuffer::<8>::try_from("ab").unwrap();
+        buffer.fill_remaining('€').unwrap();
+        assert_eq!(buffer, "ab€€");
+
+        let mut buffer = WriteBuffer::<8>::try_from("abc").unwrap();
+        assert_eq!(
+            buffer.fill_remaining('€'),
+            Err(CapacityError {
+                requested: 6,
+                available: 5,
+            })
+        );
+        assert_eq!(buffer, "abc");
+    }
+
+    #[test]
+    fn test_repeat_str() {
+        let mut buffer: WriteBuffer<10> = WriteBuffer::new();
+        buffer.repeat_str("=", 4).unwrap();
+        buffer.repeat_str("ab", 3).unwrap();
+        buffer.repeat_str("", 100).unwrap();
+        assert_eq!(buffer, "====ababab");
+
+        buffer.truncate(4);
+        assert_eq!(
+            buffer.repeat_str("ab", 4),
+            Err(CapacityError {
+                requested: 8,
+                available: 6,
+            })
+        );
+        assert_eq!(buffer, "====");
+
+        assert_eq!(
+            buffer.repeat_str("ab", usize::MAX),
+            Err(CapacityError {
+                requested: usize::MAX,
+                available: 6,
+            })
+        );
+        assert_eq!(buffer, "====");
+    }
+
+    #[test]
+    fn test_trim_in_place() {
+        let mut buffer = WriteBuffer::<20>::try_from(" \tvalue é \t ").unwrap();
+        buffer.trim_end_in_place();
+        assert_eq!(buffer, " \tvalue é");
+        buffer.trim_start_in_place();
+        assert_eq!(buffer, "value é");
+        buffer.trim_start_in_place();
+        buffer.trim_end_in_place();
+        assert_eq!(buffer, "value é");
+
+        let mut buffer = WriteBuffer::<20>::try_from(" \t ").unwrap();
+        buffer.trim_end_in_place();
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn test_add_assign() {
+        let mut buffer: WriteBuffer<8> = WriteBuffer::new();
+        buffer += "foo";
+        buffer += '-';
+        buffer += "bar";
+        assert_eq!(buffer, "foo-bar");
+    }
+
+    #[test]
+    #[should_panic(expected = "buffer capacity exceeded: requested 3 bytes, 1 available")]
+    fn test_add_assign_panics_on_overflow() {
+        let mut buffer: WriteBuffer<8> = WriteBuffer::new();
+        buffer += "foo-bar";
+        buffer += "baz";
+    }
+
+    #[test]
+    fn test_write_joined() {
+        let mut buffer: WriteBuffer<8> = WriteBuffer::new();
+        buffer.write_joined([], ",").unwrap();
+        assert!(buffer.is_empty());
+        buffer.write_joined(["a"], ",").unwrap();
+        assert_eq!(buffer, "a");
+
+        buffer.reset();
+        buffer.write_joined(["a", "b", "c"], ", ").unwrap();
+        assert_eq!(buffer, "a, b, c");
+
+        buffer.reset();
+        buffer.push_str("x").unwrap();
+        assert_eq!(
+            buffer.write_joined(["ab", "cd", "ef"], ","),
+            Err(CapacityError {
+                requested: 8,
+                available: 7,
+            })
+        );
+        assert_eq!(buffer, "x");
+    }
+
+    #[test]
+    fn test_write_hex() {
+        let mut buffer: WriteBuffer<20> = WriteBuffer::new();
+        buffer.write_hex_lower(&[0xde, 0xad, 0xbe, 0xef]).unwrap();
+        buffer.push('/').unwrap();
+        buffer.write_hex_upper(&[0xde, 0xad, 0xbe, 0xef]).unwrap();
+        assert_eq!(buffer, "deadbeef/DEADBEEF");
+        buffer.write_hex_lower(&[]).unwrap();
+
+        assert_eq!(
+            buffer.write_hex_lower(&[0x00, 0x01]),
+            Err(CapacityError {
+                requested: 4,
+                available: 3,
+            })
+        );
+        assert_eq!(buffer, "deadbeef/DEADBEEF");
+        buffer.write_hex_upper(&[0x0a]).unwrap();
+        assert_eq!(buffer, "deadbeef/DEADBEEF0A");
+    }
+
+    #[test]
+    fn test_borrow_str() {
+        use std::collections::HashMap;
+
+        let mut map = HashMap::new();
+        map.insert(WriteBuffer::<8>::try_from("key").unwrap(), 1);
+        map.insert(WriteBuffer::<8>::try_from("other").unwrap(), 2);
+        assert_eq!(map.get("key"), Some(&1));
+        assert_eq!(map.get("other"), Some(&2));
+        assert_eq!(map.get("missing"), None);
+    }
+
+    #[test]
+    fn test_modify_bytes() {
+        let mut buffer = WriteBuffer::<8>::try_from("abc").unwrap();
+        buffer
+            .modify_bytes(|bytes| bytes.iter_mut().for_each(|b| *b ^= 0x20))
+            .unwrap();
+        assert_eq!(buffer, "ABC");
+
+        let err = buffer.modify_bytes(|bytes| bytes[1] = 0xff).unwrap_err();
+        assert_eq!(err.valid_up_to(), 1);
+        assert_eq!(buffer.len(), 3);
+        assert!(buffer.try_as_str().is_err());
+
+        buffer.modify_bytes(|bytes| bytes[1] = b'b').unwrap();
+        assert_eq!(buffer, "AbC");
+    }
+
+    #[test]
+    fn test_lines() {
+        let mut buffer: WriteBuffer<20> = WriteBuffer::new();
+        assert_eq!(buffer.line_count(), 0);
+
+        buffer.push_str("a\nb").unwrap();
+        assert_eq!(buffer.line_count(), 2);
+        assert_eq!(buffer.lines().collect::<Vec<_>>(), ["a", "b"]);
+
+        buffer.push_str("\r\n").unwrap();
+        assert_eq!(buffer.line_count(), 2);
+        assert_eq!(buffer.lines().last(), Some("b"));
+
+        buffer.push_str("\n").unwrap();
+        assert_eq!(buffer.line_count(), 3);
+    }
+
+    #[test]
+    fn test_write_padded() {
+        let mut buffer: WriteBuffer<32> = WriteBuffer::new();
+        buffer.write_padded("ab", 5, Align::Left, '.').unwrap();
+        buffer.write_padded("ab", 5, Align::Right, '.').unwrap();
+        buffer.write_padded("ab", 5, Align::Center, '.').unwrap();
+        assert_eq!(buffer, "ab......ab.ab..");
+
+        buffer.reset();
+        buffer.write_padded("é", 4, Align::Center, '─').unwrap();
+        assert_eq!(buffer, "─é──");
+
+        buffer.reset();
+        buffer
+            .write_padded("toolong", 3, Align::Right, ' ')
+            .unwrap();
+        assert_eq!(buffer, "toolong");
+
+        let mut buffer: WriteBuffer<8> = WriteBuffer::new();
+        buffer.write_padded("ab", 4, Align::Left, '─').unwrap();
+        assert!(buffer.is_full());
+        buffer.reset();
+        assert_eq!(
+            buffer.write_padded("ab", 5, Align::Right, '─'),
+            Err(CapacityError {
+                requested: 11,
+                available: 8,
+            })
+        );
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn test_find() {
+        let buffer = WriteBuffer::<20>::try_from("é=1;b=2;c=3").unwrap();
+        assert_eq!(buffer.find("="), Some(2));
+        assert_eq!(buffer.find("c"), Some(9));
+        assert_eq!(buffer.find("d"), None);
+        assert_eq!(buffer.rfind(";"), Some(8));
+        assert_eq!(buffer.rfind("d"), None);
+    }
+
+    #[test]
+    fn test_split_off() {
+        let mut buffer = WriteBuffer::<20>::try_from("key=vé").unwrap();
+        let tail = buffer.split_off(3).unwrap();
+        assert_eq!(buffer, "key");
+        assert_eq!(tail, "=vé");
+
+        let mut tail = tail;
+        assert_eq!(
+            tail.split_off(3),
+            Err(SplitError::NotCharBoundary(CharBoundaryError { index: 3 }))
+        );
+        assert_eq!(
+            tail.split_off(5),
+            Err(SplitError::OutOfBounds { index: 5, len: 4 })
+        );
+        assert_eq!(tail, "=vé");
 
-        // New cursor after write
-        let new_cursor = self.cursor + bytes.len();
+        assert!(tail.split_off(4).unwrap().is_empty());
+        assert_eq!(tail.split_off(0).unwrap(), "=vé");
+        assert!(tail.is_empty());
+    }
+
+    #[test]
+    fn test_insert_str() {
+        let mut buffer = WriteBuffer::<12>::try_from("héllo").unwrap();
+        buffer.insert_str(0, ">").unwrap();
+        assert_eq!(buffer, ">héllo");
+        buffer.insert_str(4, "é").unwrap();
+        assert_eq!(buffer, ">hééllo");
+        buffer.insert_str(buffer.len(), "!").unwrap();
+        assert_eq!(buffer, ">hééllo!");
+
+        assert_eq!(
+            buffer.insert_str(3, "x"),
+            Err(InsertError::NotCharBoundary(CharBoundaryError { index: 3 }))
+        );
+        assert_eq!(
+            buffer.insert_str(11, "x"),
+            Err(InsertError::OutOfBounds { index: 11, len: 10 })
+        );
+        assert_eq!(
+            buffer.insert_str(1, "abc"),
+            Err(InsertError::Capacity(CapacityError {
+                requested: 3,
+                available: 2,
+            }))
+        );
+        assert_eq!(buffer, ">hééllo!");
+    }
+
+    #[test]
+    fn test_remove_range() {
+        let mut buffer = WriteBuffer::<20>::try_from("abcé-xy-éz").unwrap();
+        buffer.remove_range(0..2).unwrap();
+        assert_eq!(buffer, "cé-xy-éz");
+        buffer.remove_range(3..7).unwrap();
+        assert_eq!(buffer, "cééz");
+        buffer.remove_range(1..1).unwrap();
+        assert_eq!(buffer, "cééz");
+
+        assert_eq!(
+            buffer.remove_range(2..4),
+            Err(RangeError::NotCharBoundary(CharBoundaryError { index: 2 }))
+        );
+        assert_eq!(
+            buffer.remove_range(1..2),
+            Err(RangeError::NotCharBoundary(CharBoundaryError { index: 2 }))
+        );
+        #[allow(clippy::reversed_empty_ranges)]
+        let reversed = 3..1;
+        assert_eq!(
+            buffer.remove_range(reversed),
+            Err(RangeError::StartAfterEnd { start: 3, end: 1 })
+        );
+        assert_eq!(
+            buffer.remove_range(1..7),
+            Err(RangeError::OutOfBounds { end: 7, len: 6 })
+        );
+        assert_eq!(buffer, "cééz");
+    }
+
+    #[test]
+    fn test_is_char_boundary() {
+        let buffer = WriteBuffer::<20>::try_from("a€b").unwrap();
+        assert!(buffer.is_char_boundary(0));
+        assert!(buffer.is_char_boundary(1));
+        assert!(!buffer.is_char_boundary(2));
+        assert!(!buffer.is_char_boundary(3));
+        assert!(buffer.is_char_boundary(4));
+        assert!(buffer.is_char_boundary(5));
+        assert!(!buffer.is_char_boundary(6));
+        assert!(!buffer.is_char_boundary(20));
+    }
+
+    #[test]
+    fn test_write_fmt_checked() {
+        let mut buffer: WriteBuffer<8> = WriteBuffer::with_policy(OverflowPolicy::Truncate);
+        buffer
+            .write_fmt_checked(format_args!("{}-{}", 1, 2))
+            .unwrap();
+        assert_eq!(buffer, "1-2");
+
+        let err = buffer
+            .write_fmt_checked(format_args!("{}-{}", 345, 678))
+            .unwrap_err();
+        assert_eq!(
+            err,
+            CapacityError {
+                requested: 7,
+                available: 5,
+            }
+        );
+        assert_eq!(buffer, "1-2");
 
-        // If we would exceed the capacity of the buffer, we fail
-        if new_cursor > N {
-            return Err(fmt::Error);
+        crate::ewrite!(buffer, "{:>5}", 4).unwrap();
+        assert_eq!(buffer, "1-2    4");
+        assert!(crate::ewrite!(buffer, "!").is_err());
+    }
+
+    #[test]
+    fn test_concat() {
+        let mut buffer = WriteBuffer::<8>::try_from("ab").unwrap();
+        let small = WriteBuffer::<4>::try_from("cé").unwrap();
+        let big = WriteBuffer::<32>::try_from("fghi").unwrap();
+        buffer.concat(&small).unwrap();
+        buffer.concat(&WriteBuffer::<0>::new()).unwrap();
+        assert_eq!(buffer, "abcé");
+
+        assert_eq!(
+            buffer.concat(&big),
+            Err(CapacityError {
+                requested: 4,
+                available: 3,
+            })
+        );
+        assert_eq!(buffer, "abcé");
+    }
+
+    #[test]
+    fn test_first_and_last_char() {
+        let buffer = WriteBuffer::<20>::try_from("abc").unwrap();
+        assert_eq!(buffer.first_char(), Some('a'));
+        assert_eq!(buffer.last_char(), Some('c'));
+
+        let buffer = WriteBuffer::<20>::try_from("é🦀").unwrap();
+        assert_eq!(buffer.first_char(), Some('é'));
+        assert_eq!(buffer.last_char(), Some('🦀'));
+
+        let buffer = WriteBuffer::<20>::new();
+        assert_eq!(buffer.first_char(), None);
+        assert_eq!(buffer.last_char(), None);
+    }
+
+    #[test]
+    fn test_assert_fits() {
+        const _: () = WriteBuffer::<8>::assert_fits::<8>();
+        WriteBuffer::<8>::assert_fits::<0>();
+    }
+
+    #[test]
+    fn test_write_integers() {
+        let mut buffer: WriteBuffer<32> = WriteBuffer::new();
+        macro_rules! check {
+            ($method:ident, $($n:expr),*) => {
+                $(
+                    buffer.reset();
+                    buffer.$method($n).unwrap();
+                    assert_eq!(buffer, format!("{}", $n).as_str());
+                )*
+            };
         }
+        check!(write_u8, 0, 7, u8::MAX);
+        check!(write_u16, 0, 10, u16::MAX);
+        check!(write_u32, 0, 1_000_000, u32::MAX);
+        check!(write_u64, 0, 42, u64::MAX);
+        check!(write_i8, 0, -1, i8::MIN, i8::MAX);
+        check!(write_i16, 0, -100, i16::MIN, i16::MAX);
+        check!(write_i32, 0, -9, i32::MIN, i32::MAX);
+        check!(write_i64, 0, -1234, i64::MIN, i64::MAX);
 
-        // Efficiently copy the bytes into the bufffer
-        self.buffer[self.cursor..new_cursor].copy_from_slice(bytes);
+        let mut buffer: WriteBuffer<4> = WriteBuffer::new();
+        buffer.write_u8(12).unwrap();
+        assert_eq!(
+            buffer.write_i16(-100),
+            Err(CapacityError {
+                requested: 4,
+                available: 2,
+            })
+        );
+        assert_eq!(buffer, "12");
+        buffer.write_i8(-1).unwrap();
+        assert_eq!(buffer, "12-1");
+    }
 
-        // Update the cursor
-        self.cursor = new_cursor;
+    #[test]
+    fn test_drain_to() {
+        let mut buffer: WriteBuffer<10> = WriteBuffer::new();
+        let mut out: WriteBuffer<10> = WriteBuffer::new();
+        buffer.push_str("hello").unwrap();
+        buffer.drain_to(&mut out).unwrap();
+        assert!(buffer.is_empty());
+        assert_eq!(out, "hello");
 
-        Ok(())
+        struct Failing;
+        impl Write for Failing {
+            fn write_str(&mut self, _: &str) -> core::fmt::Result {
+                Err(core::fmt::Error)
+            }
+        }
+        buffer.push_str("retry").unwrap();
+        assert!(buffer.drain_to(&mut Failing).is_err());
+        assert_eq!(buffer, "retry");
     }
-}
 
-impl<const N: usize> Display for WriteBuffer<N> {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.as_str())
+    #[test]
+    fn test_ord() {
+        let mut buffers = [
+            WriteBuffer::<8>::try_from("pear").unwrap(),
+            WriteBuffer::<8>::try_from("apple").unwrap(),
+            WriteBuffer::<8>::try_from("fig").unwrap(),
+        ];
+        buffers.sort();
+        assert_eq!(buffers[0], "apple");
+        assert_eq!(buffers[1], "fig");
+        assert_eq!(buffers[2], "pear");
+        assert!(buffers[0] < buffers[1]);
     }
-}
 
-#[cfg(test)]
-mod test {
-    use super::WriteBuffer;
-    use core::fmt::Write;
+    #[test]
+    fn test_retain() {
+        let mut buffer = WriteBuffer::<16>::try_from(" a b  c ").unwrap();
+        buffer.retain(|c| c != ' ');
+        assert_eq!(buffer, "abc");
+
+        let mut buffer = WriteBuffer::<16>::try_from("aàbèc").unwrap();
+        buffer.retain(|c| c != 'à');
+        assert_eq!(buffer, "abèc");
+        buffer.retain(|c| c.is_ascii());
+        assert_eq!(buffer, "abc");
+        buffer.retain(|_| false);
+        assert!(buffer.is_empty());
+    }
 
     #[test]
-    fn test_write_wrapper() {
-        let x = 123;
+    fn test_make_ascii_case() {
+        let mut buffer = WriteBuffer::<16>::try_from("Àbc-dé").unwrap();
+        buffer.make_ascii_uppercase();
+        assert_eq!(buffer, "ÀBC-Dé");
+        buffer.make_ascii_lowercase();
+        assert_eq!(buffer, "Àbc-dé");
+        assert_eq!(buffer.len(), "Àbc-dé".len());
+    }
 
-        let mut buffer: WriteBuffer<20> = WriteBuffer::new();
-        write!(buffer, "{}", x).unwrap();
-        assert_eq!(&buffer.as_slice()[0..3], b"123");
+    #[test]
+    fn test_write_json_escaped() {
+        let s = "say \"hi\"\\\n\ttab\u{1}\u{1f}é";
+        let mut buffer: WriteBuffer<64> = WriteBuffer::new();
+        buffer.write_json_escaped(s).unwrap();
+        let expected = serde_json::to_string(s).unwrap();
+        assert_eq!(buffer, &expected[1..expected.len() - 1]);
 
-        buffer.reset();
-        let x = 2.242424;
-        write!(buffer, "{:.2}", x).unwrap();
-        assert_eq!(buffer.as_slice(), b"2.24");
+        let mut buffer: WriteBuffer<8> = WriteBuffer::new();
+        buffer.push_str("ab").unwrap();
+        assert_eq!(
+            buffer.write_json_escaped("\"quoted\""),
+            Err(CapacityError {
+                requested: 10,
+                available: 6,
+            })
+        );
+        assert_eq!(buffer, "ab");
+        buffer.write_json_escaped("a\"b").unwrap();
+        assert_eq!(buffer, "aba\\\"b");
+    }
+
+    #[test]
+    fn test_from_array() {
+        let mut array = [0u8; 8];
+        array[..5].copy_from_slice(b"hello");
+        let mut buffer = WriteBuffer::from_array(array, 5).unwrap();
+        assert_eq!(buffer, "hello");
+        buffer.push_str("!").unwrap();
+        assert_eq!(buffer, "hello!");
+
+        assert_eq!(
+            WriteBuffer::from_array(array, 9).unwrap_err(),
+            FromArrayError::OutOfBounds {
+                len: 9,
+                capacity: 8,
+            }
+        );
+
+        array[1] = 0xff;
+        assert!(matches!(
+            WriteBuffer::from_array(array, 5),
+            Err(FromArrayError::Utf8(_))
+        ));
+        assert_eq!(WriteBuffer::from_array(array, 1).unwrap(), "h");
+    }
+
+    #[test]
+    fn test_can_fit() {
+        let mut buffer: WriteBuffer<5> = WriteBuffer::new();
+        buffer.push_str("ab").unwrap();
+        assert!(buffer.can_fit(0));
+        assert!(buffer.can_fit(3));
+        assert!(!buffer.can_fit(4));
+    }
+
+    #[test]
+    fn test_shortfall() {
+        let mut buffer: WriteBuffer<5> = WriteBuffer::new();
+        buffer.push_str("ab").unwrap();
+        assert_eq!(buffer.shortfall(3), 0);
+        assert_eq!(buffer.shortfall(6), 3);
+    }
+
+    #[test]
+    fn test_write_f32() {
+        let mut buffer: WriteBuffer<64> = WriteBuffer::new();
+        let values = [
+            0.0,
+            -0.0,
+            1.0,
+            -1.5,
+            0.125,
+            0.375,
+            2.5,
+            0.995,
+            -0.005,
+            1.005,
+            2.71235,
+            99.999,
+            -123.456,
+            1e-7,
+            16777216.0,
+            1e15,
+            1e30,
+            f32::MAX,
+            f32::NAN,
+            f32::INFINITY,
+        ];
+        for &x in values.iter() {
+            for decimals in [0, 1, 2, 3, 6, 12, 15].iter() {
+                buffer.reset();
+                buffer.write_f32(x, *decimals).unwrap();
+                assert_eq!(buffer, format!("{:.*}", decimals, x).as_str());
+            }
+        }
+
+        let mut buffer: WriteBuffer<4> = WriteBuffer::new();
+        buffer.write_f32(1.25, 1).unwrap();
+        assert_eq!(buffer, "1.2");
+        assert_eq!(
+            buffer.write_f32(-0.5, 0),
+            Err(CapacityError {
+                requested: 2,
+                available: 1,
+            })
+        );
+        assert_eq!(buffer, "1.2");
+        assert_eq!(
+            buffer.write_f32(1.5, 70_000),
+            Err(CapacityError {
+                requested: 70_002,
+                available: 1,
+            })
+        );
+        assert_eq!(buffer, "1.2");
+    }
+
+    #[test]
+    fn test_try_from_iter() {
+        let buffer =
+            WriteBuffer::<8>::try_from_chars("a b c".chars().filter(|c| *c != ' ')).unwrap();
+        assert_eq!(buffer, "abc");
+        assert_eq!(
+            WriteBuffer::<2>::try_from_chars("abc".chars()).unwrap_err(),
+            CapacityError {
+                requested: 1,
+                available: 0,
+            }
+        );
+
+        let buffer = WriteBuffer::<8>::try_from_strs(["ab", "", "cd"].iter().copied()).unwrap();
+        assert_eq!(buffer, "abcd");
+        assert!(WriteBuffer::<3>::try_from_strs(["ab", "cd"].iter().copied()).is_err());
+    }
+
+    #[test]
+    fn test_write_bytes_lossy() {
+        let bytes = b"ab\xc3(cd\xe2\x82";
+        let mut buffer: WriteBuffer<16> = WriteBuffer::new();
+        buffer.write_bytes_lossy(bytes).unwrap();
+        assert_eq!(buffer, String::from_utf8_lossy(bytes).as_ref());
+        assert_eq!(buffer, "ab\u{FFFD}(cd\u{FFFD}");
+
+        let mut buffer: WriteBuffer<5> = WriteBuffer::new();
+        buffer.write_bytes_lossy(b"a\xffb").unwrap();
+        assert_eq!(buffer, "a\u{FFFD}b");
+        assert_eq!(
+            buffer.write_bytes_lossy(b"\xff"),
+            Err(CapacityError {
+                requested: 3,
+                available: 0,
+            })
+        );
+        assert_eq!(buffer, "a\u{FFFD}b");
+    }
+
+    #[test]
+    fn test_set_cursor() {
+        let mut buffer = WriteBuffer::<8>::try_from("aèb").unwrap();
+        assert_eq!(buffer.cursor(), 4);
+        assert_eq!(
+            buffer.set_cursor(5),
+            Err(SetCursorError::OutOfBounds { index: 5, len: 4 })
+        );
+        assert_eq!(
+            buffer.set_cursor(2),
+            Err(SetCursorError::NotCharBoundary(CharBoundaryError {
+                index: 2
+            }))
+        );
+        assert_eq!(buffer, "aèb");
+        buffer.set_cursor(4).unwrap();
+        assert_eq!(buffer, "aèb");
+        buffer.set_cursor(3).unwrap();
+        assert_eq!(buffer, "aè");
+        assert_eq!(buffer.cursor(), 3);
+        buffer.set_cursor(0).unwrap();
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn test_write_through_mut_ref() {
+        fn write_answer<W: Write>(mut w: W) {
+            write!(w, "{}", 42).unwrap();
+        }
+        let mut buffer: WriteBuffer<8> = WriteBuffer::new();
+        write_answer(&mut buffer);
+        write_answer(&mut buffer);
+        assert_eq!(buffer, "4242");
+    }
 
+    #[test]
+    fn test_max_len_seen() {
+        let mut buffer: WriteBuffer<16> = WriteBuffer::new();
+        assert_eq!(buffer.max_len_seen(), 0);
+        buffer.push_str("hello").unwrap();
         buffer.reset();
-        let x = 20;
-        write!(buffer, "Longer than {} characters sentence", x).unwrap_err();
+        write!(buffer, "{}", 12).unwrap();
+        assert_eq!(buffer.max_len_seen(), 5);
+        buffer.reset();
+        buffer.write_hex_lower(b"abcd").unwrap();
+        buffer.pop();
+        assert_eq!(buffer.max_len_seen(), 8);
+        assert!(buffer.push_str("too long for it").is_err());
+        assert_eq!(buffer.max_len_seen(), 8);
 
+        buffer.reset_high_water();
+        assert_eq!(buffer.max_len_seen(), 7);
         buffer.reset();
-        write!(buffer, "{}", "1").unwrap();
-        write!(buffer, "{}", "2").unwrap();
-        assert_eq!(buffer.as_slice(), b"12");
+        buffer.reset_high_water();
+        assert_eq!(buffer.max_len_seen(), 0);
     }
 
     #[test]
-    fn test_display() {
-        let x = 123;
+    fn test_extend() {
+        let mut buffer: WriteBuffer<8> = WriteBuffer::new();
+        buffer.extend("a-b".chars().filter(|c| *c != '-'));
+        buffer.extend(["cd", "ef"].iter().copied());
+        assert_eq!(buffer, "abcdef");
+    }
 
-        let mut buffer: WriteBuffer<20> = WriteBuffer::new();
-        write!(buffer, "{}", x).unwrap();
-        assert_eq!("123", format!("{}", buffer));
+    #[test]
+    #[should_panic(expected = "buffer capacity exceeded: requested 1 bytes, 0 available")]
+    fn test_extend_panics_on_overflow() {
+        let mut buffer: WriteBuffer<2> = WriteBuffer::new();
+        buffer.extend("abc".chars());
     }
 
     #[test]
-    fn test_as_str_mut() {
-        let mut buffer: WriteBuffer<20> = WriteBuffer::new();
-        write!(buffer, "hello world").unwrap();
-        buffer.as_str_mut().make_ascii_uppercase();
+    fn test_write_bool_and_char_repeated() {
+        let mut buffer: WriteBuffer<10> = WriteBuffer::new();
+        buffer.write_bool(true).unwrap();
+        buffer.write_bool(false).unwrap();
+        assert_eq!(buffer, "truefalse");
+        assert!(buffer.write_bool(true).is_err());
+        assert_eq!(buffer, "truefalse");
 
-        assert_eq!(buffer.as_str(), "HELLO WORLD");
+        let mut buffer: WriteBuffer<7> = WriteBuffer::new();
+        buffer.write_char_repeated('é', 3).unwrap();
+        buffer.write_char_repeated('x', 0).unwrap();
+        assert_eq!(buffer, "ééé");
+        assert_eq!(
+            buffer.write_char_repeated('-', 2),
+            Err(CapacityError {
+                requested: 2,
+                available: 1,
+            })
+        );
+        assert_eq!(buffer, "ééé");
     }
 
     #[test]
-    fn test_is_empty_is_full_and_overflow() {
-        let mut buffer: WriteBuffer<10> = WriteBuffer::new();
+    fn test_as_str_checked() {
+        let mut buffer: WriteBuffer<8> = WriteBuffer::new();
+        write!(buffer, "a{}", 1).unwrap();
+        buffer.push_str("b").unwrap();
+        assert_eq!(buffer.as_str_checked(), Ok("a1b"));
+        buffer.write_bytes(&[0xff]).unwrap();
+        assert!(buffer.as_str_checked().is_err());
+    }
+
+    #[cfg(feature = "heapless")]
+    #[test]
+    fn test_heapless() {
+        let buffer = WriteBuffer::<8>::try_from("hello").unwrap();
+        let string = buffer.to_heapless_string::<8>().unwrap();
+        assert_eq!(string.as_str(), "hello");
+        assert_eq!(
+            buffer.to_heapless_string::<4>().unwrap_err(),
+            CapacityError {
+                requested: 5,
+                available: 4,
+            }
+        );
+
+        let back = WriteBuffer::<5>::try_from(string.clone()).unwrap();
+        assert_eq!(back, buffer);
+        assert!(WriteBuffer::<4>::try_from(string).is_err());
+    }
+
+    #[test]
+    fn test_split_at() {
+        let buffer = WriteBuffer::<8>::try_from("aèb").unwrap();
+        assert_eq!(buffer.split_at(1), Some(("a", "èb")));
+        assert_eq!(buffer.split_at(2), None);
+        assert_eq!(buffer.split_at(4), Some(("aèb", "")));
+        assert_eq!(buffer.split_at(5), None);
+        assert_eq!(buffer.split_at(0), Some(("", "aèb")));
+    }
+
+    #[test]
+    fn test_count_chars() {
+        let buffer = WriteBuffer::<16>::try_from("aé€😀").unwrap();
+        assert_eq!(buffer.count_chars(), 4);
+        assert_eq!(buffer.len(), 10);
+        assert_eq!(WriteBuffer::<4>::new().count_chars(), 0);
+    }
+
+    #[test]
+    fn test_transaction() {
+        let mut buffer: WriteBuffer<8> = WriteBuffer::new();
+        buffer.push_str("a").unwrap();
+
+        let mut transaction = buffer.transaction();
+        write!(transaction, "b{}", 1).unwrap();
+        transaction.commit();
+        assert_eq!(buffer, "ab1");
+
+        let mut transaction = buffer.transaction();
+        write!(transaction, "c{}", 2).unwrap();
+        drop(transaction);
+        assert_eq!(buffer, "ab1");
+
+        buffer.set_policy(OverflowPolicy::Truncate);
+        let mut transaction: Transaction<'_, 8> = buffer.transaction();
+        assert!(write!(transaction, "{}", 123_456).is_err());
+        write!(transaction, "{}", 12).unwrap();
+        transaction.commit();
+        assert_eq!(buffer, "ab112");
+    }
+
+    #[test]
+    fn test_pad_to() {
+        let mut buffer: WriteBuffer<16> = WriteBuffer::new();
+        buffer.push_str("name").unwrap();
+        buffer.pad_to(' ', 8).unwrap();
+        buffer.push_str("|").unwrap();
+        assert_eq!(buffer, "name    |");
+        buffer.pad_to(' ', 4).unwrap();
+        assert_eq!(buffer, "name    |");
+
+        buffer.pad_to('é', 12).unwrap();
+        assert_eq!(buffer, "name    |éé");
+        assert_eq!(buffer.len(), 13);
+
+        let mut buffer: WriteBuffer<4> = WriteBuffer::new();
+        buffer.push_str("a").unwrap();
+        assert_eq!(
+            buffer.pad_to('é', 4),
+            Err(CapacityError {
+                requested: 4,
+                available: 3,
+            })
+        );
+        assert_eq!(buffer, "a");
+        assert!(buffer.pad_to('-', 5).is_err());
+        assert_eq!(buffer, "a");
+    }
+
+    #[test]
+    fn test_write_display_list() {
+        let mut buffer: WriteBuffer<16> = WriteBuffer::new();
+        buffer
+            .write_display_list([1, 22, 333].iter(), ", ")
+            .unwrap();
+        assert_eq!(buffer, "1, 22, 333");
+        buffer.reset();
+        buffer
+            .write_display_list(core::iter::empty::<u8>(), ", ")
+            .unwrap();
         assert!(buffer.is_empty());
-        write!(buffer, "0123456789").unwrap();
+
+        buffer.push_str("x=").unwrap();
+        assert_eq!(
+            buffer.write_display_list([1.5, -2.0, 100.25, 7.0].iter(), "; "),
+            Err(CapacityError {
+                requested: 15,
+                available: 14,
+            })
+        );
+        assert_eq!(buffer, "x=");
+        assert_eq!(
+            buffer.write_display_list(0.., ","),
+            Err(CapacityError {
+                requested: 15,
+                available: 14,
+            })
+        );
+        assert_eq!(buffer, "x=");
+    }
+
+    #[test]
+    fn test_split() {
+        let buffer = WriteBuffer::<16>::try_from("a,bb,,c").unwrap();
+        let mut split = buffer.split(",");
+        assert_eq!(split.next(), Some("a"));
+        assert_eq!(split.next(), Some("bb"));
+        assert_eq!(split.next(), Some(""));
+        assert_eq!(split.next(), Some("c"));
+        assert_eq!(split.next(), None);
+
+        let mut rsplit = buffer.rsplit(",,");
+        assert_eq!(rsplit.next(), Some("c"));
+        assert_eq!(rsplit.next(), Some("a,bb"));
+        assert_eq!(rsplit.next(), None);
+    }
+
+    #[cfg(feature = "panic-on-overflow")]
+    #[test]
+    #[should_panic(expected = "buffer capacity exceeded: requested 3 bytes, 2 available")]
+    fn test_panic_on_overflow() {
+        let mut buffer: WriteBuffer<4> = WriteBuffer::new();
+        write!(buffer, "ab").unwrap();
+        let _ = write!(buffer, "{}", 123);
+    }
+
+    #[test]
+    fn test_as_mut_slice_unchecked() {
+        let mut buffer = WriteBuffer::<8>::try_from("a-b-c").unwrap();
+        // SAFETY: replacing ASCII bytes with ASCII bytes keeps the content
+        // valid UTF-8
+        let bytes = unsafe { buffer.as_mut_slice_unchecked() };
+        assert_eq!(bytes.len(), 5);
+        for byte in bytes.iter_mut().filter(|b| **b == b'-') {
+            *byte = b'+';
+        }
+        assert_eq!(buffer, "a+b+c");
+    }
+
+    #[test]
+    fn test_with_content_truncated() {
+        assert_eq!(WriteBuffer::<8>::with_content_truncated("abc"), "abc");
+        assert_eq!(WriteBuffer::<3>::with_content_truncated("abc"), "abc");
+        assert_eq!(WriteBuffer::<3>::with_content_truncated("aébc"), "aé");
+        assert_eq!(WriteBuffer::<2>::with_content_truncated("aé"), "a");
+        assert!(WriteBuffer::<0>::with_content_truncated("a").is_empty());
+    }
+
+    #[test]
+    fn test_byte_at_and_char_at() {
+        let buffer = WriteBuffer::<8>::try_from("aéb").unwrap();
+        assert_eq!(buffer.byte_at(0), Some(b'a'));
+        assert_eq!(buffer.byte_at(2), Some(0xa9));
+        assert_eq!(buffer.byte_at(4), None);
+
+        assert_eq!(buffer.char_at(1), Some('é'));
+        assert_eq!(buffer.char_at(2), None);
+        assert_eq!(buffer.char_at(3), Some('b'));
+        assert_eq!(buffer.char_at(4), None);
+        assert_eq!(buffer.char_at(9), None);
+    }
+
+    #[test]
+    fn test_write_u32_padded() {
+        let mut buffer: WriteBuffer<12> = WriteBuffer::new();
+        buffer.write_u32_padded(7, 3, '0').unwrap();
+        assert_eq!(buffer, "007");
+        buffer.write_u32_padded(12345, 3, '0').unwrap();
+        assert_eq!(buffer, "00712345");
+        buffer.write_u32_padded(0, 2, ' ').unwrap();
+        assert_eq!(buffer, "00712345 0");
+        assert_eq!(
+            buffer.write_u32_padded(1, 3, '0'),
+            Err(PadError::Capacity(CapacityError {
+                requested: 3,
+                available: 2,
+            }))
+        );
+        assert_eq!(
+            buffer.write_u32_padded(1, 2, '·'),
+            Err(PadError::MultiByteFill('·'))
+        );
+        assert_eq!(buffer, "00712345 0");
+    }
+
+    #[test]
+    fn test_truncated_flag() {
+        let mut buffer: WriteBuffer<4> = WriteBuffer::with_policy(OverflowPolicy::Truncate);
+        let _ = write!(buffer, "ab");
+        assert!(!buffer.truncated());
+        let _ = write!(buffer, "cd");
+        assert!(!buffer.truncated());
+        let _ = write!(buffer, "e");
+        assert!(buffer.truncated());
+        assert_eq!(buffer, "abcd");
+
+        buffer.clear_truncated_flag();
+        assert!(!buffer.truncated());
+        assert_eq!(buffer, "abcd");
+        let _ = write!(buffer, "{}", 1);
+        assert!(buffer.truncated());
+        buffer.reset();
+        assert!(!buffer.truncated());
+
+        buffer.set_policy(OverflowPolicy::Wrap);
+        let _ = write!(buffer, "abc");
+        let _ = write!(buffer, "de");
+        assert!(buffer.truncated());
+        assert_eq!(buffer, "bcde");
+        buffer.reset();
+        let _ = write!(buffer, "abcd");
+        assert!(!buffer.truncated());
+    }
+
+    #[test]
+    fn test_prepend_str() {
+        let mut buffer: WriteBuffer<8> = WriteBuffer::new();
+        buffer.prepend_str("bc").unwrap();
+        assert_eq!(buffer, "bc");
+        buffer.prepend_str("é").unwrap();
+        assert_eq!(buffer, "ébc");
+        assert_eq!(
+            buffer.prepend_str("12345"),
+            Err(CapacityError {
+                requested: 5,
+                available: 4,
+            })
+        );
+        assert_eq!(buffer, "ébc");
+        buffer.prepend_str("1234").unwrap();
+        assert_eq!(buffer, "1234ébc");
+    }
+
+    #[test]
+    fn test_ewrite_buffer() {
+        const PREFIX: &str = "temp";
+        let buffer = crate::ewrite_buffer!(PREFIX, ": ", "21", "°C");
+        assert_eq!(buffer, "temp: 21°C");
+        assert_eq!(buffer.capacity(), "temp: 21°C".len());
         assert!(buffer.is_full());
-        assert_eq!(write!(buffer, "!"), Err(core::fmt::Error));
+
+        let empty = crate::ewrite_buffer!();
+        assert_eq!(empty.capacity(), 0);
+        let single = crate::ewrite_buffer!("é",);
+        assert_eq!(single.capacity(), 2);
+
+        const BUFFER: &str = "abc";
+        const LEN: &str = "de";
+        const PARTS: &str = "f";
+        let shadowing = crate::ewrite_buffer!(BUFFER, "-", LEN, PARTS);
+        assert_eq!(shadowing, "abc-def");
+    }
+
+    #[test]
+    fn test_replace_range() {
+        let mut buffer = WriteBuffer::<12>::try_from("hi {name}!").unwrap();
+        buffer.replace_range(3..9, "Bo").unwrap();
+        assert_eq!(buffer, "hi Bo!");
+        buffer.replace_range(3..5, "Al").unwrap();
+        assert_eq!(buffer, "hi Al!");
+        buffer.replace_range(3..5, "Amélie").unwrap();
+        assert_eq!(buffer, "hi Amélie!");
+        assert_eq!(buffer.len(), 11);
+
+        assert_eq!(
+            buffer.replace_range(0..2, "hello"),
+            Err(ReplaceError::Capacity(CapacityError {
+                requested: 5,
+                available: 3,
+            }))
+        );
+        assert_eq!(
+            buffer.replace_range(5..6, "e"),
+            Err(ReplaceError::Range(RangeError::NotCharBoundary(
+                CharBoundaryError { index: 6 }
+            )))
+        );
+        assert_eq!(
+            buffer.replace_range(0..12, ""),
+            Err(ReplaceError::Range(RangeError::OutOfBounds {
+                end: 12,
+                len: 11
+            }))
+        );
+        assert_eq!(buffer, "hi Amélie!");
+        buffer.replace_range(0..2, "hey").unwrap();
+        assert_eq!(buffer, "hey Amélie!");
+    }
+
+    #[test]
+    fn test_write_hms() {
+        let mut buffer: WriteBuffer<16> = WriteBuffer::new();
+        for &(seconds, expected) in [
+            (0, "00:00:00"),
+            (59, "00:00:59"),
+            (3661, "01:01:01"),
+            (86399, "23:59:59"),
+            (360_000, "100:00:00"),
+            (u32::MAX, "1193046:28:15"),
+        ]
+        .iter()
+        {
+            buffer.reset();
+            buffer.write_hms(seconds).unwrap();
+            assert_eq!(buffer, expected);
+        }
+
+        let mut buffer: WriteBuffer<8> = WriteBuffer::new();
+        assert_eq!(
+            buffer.write_hms(360_000),
+            Err(CapacityError {
+                requested: 9,
+                available: 8,
+            })
+        );
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn test_char_indices() {
+        let buffer = WriteBuffer::<8>::try_from("aé€b").unwrap();
+        let mut indices = buffer.char_indices();
+        assert_eq!(indices.next(), Some((0, 'a')));
+        assert_eq!(indices.next(), Some((1, 'é')));
+        assert_eq!(indices.next(), Some((3, '€')));
+        assert_eq!(indices.next(), Some((6, 'b')));
+        assert_eq!(indices.next(), None);
+    }
+
+    #[test]
+    fn test_write_base64() {
+        let mut buffer: WriteBuffer<16> = WriteBuffer::new();
+        for &(input, expected) in [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ]
+        .iter()
+        {
+            buffer.reset();
+            buffer.write_base64(input.as_bytes()).unwrap();
+            assert_eq!(buffer, expected);
+        }
+        buffer.reset();
+        buffer.write_base64(&[0xff, 0xfe, 0x00]).unwrap();
+        assert_eq!(buffer, "//4A");
+
+        let mut buffer: WriteBuffer<6> = WriteBuffer::new();
+        assert_eq!(
+            buffer.write_base64(b"abcd"),
+            Err(CapacityError {
+                requested: 8,
+                available: 6,
+            })
+        );
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn test_keep_tail() {
+        let mut buffer = WriteBuffer::<16>::try_from("status: ok").unwrap();
+        buffer.keep_tail(20).unwrap();
+        buffer.keep_tail(10).unwrap();
+        assert_eq!(buffer, "status: ok");
+        buffer.keep_tail(2).unwrap();
+        assert_eq!(buffer, "ok");
+
+        let mut buffer = WriteBuffer::<16>::try_from("a€b").unwrap();
+        assert_eq!(
+            buffer.keep_tail(3),
+            Err(KeepTailError::NotCharBoundary(CharBoundaryError {
+                index: 2
+            }))
+        );
+        assert_eq!(buffer, "a€b");
+        buffer.keep_tail(4).unwrap();
+        assert_eq!(buffer, "€b");
+        buffer.keep_tail(0).unwrap();
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn test_try_from_char() {
+        assert_eq!(WriteBuffer::<1>::try_from('a').unwrap(), "a");
+        assert_eq!(WriteBuffer::<4>::try_from('😀').unwrap(), "😀");
+        assert_eq!(
+            WriteBuffer::<2>::try_from('😀').unwrap_err(),
+            CapacityError {
+                requested: 4,
+                available: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn test_write_centered() {
+        let mut buffer: WriteBuffer<16> = WriteBuffer::new();
+        buffer.write_centered("ab", 6, '*').unwrap();
+        assert_eq!(buffer, "**ab**");
+        buffer.reset();
+        buffer.write_centered("ab", 5, '*').unwrap();
+        assert_eq!(buffer, "*ab**");
+        buffer.reset();
+        buffer.write_centered("title", 3, '*').unwrap();
+        assert_eq!(buffer, "title");
+        buffer.reset();
+        buffer.write_centered("é", 4, '-').unwrap();
+        assert_eq!(buffer, "-é--");
+        assert_eq!(
+            buffer.write_centered("ab", 20, ' '),
+            Err(PadError::Capacity(CapacityError {
+                requested: 20,
+                available: 11,
+            }))
+        );
+        assert_eq!(
+            buffer.write_centered("ab", 4, '·'),
+            Err(PadError::MultiByteFill('·'))
+        );
+        assert_eq!(buffer, "-é--");
+    }
+
+    #[test]
+    fn test_as_ptr() {
+        let buffer = WriteBuffer::<8>::try_from("abc").unwrap();
+        let (ptr, len) = (buffer.as_ptr(), buffer.len());
+        // SAFETY: the pointer and length come from a live, unmodified buffer
+        let bytes = unsafe { core::slice::from_raw_parts(ptr, len) };
+        assert_eq!(bytes, b"abc");
+        assert_eq!(ptr, buffer.as_slice().as_ptr());
+    }
+
+    #[test]
+    fn test_as_cstr() {
+        let mut buffer = WriteBuffer::<8>::try_from("abc").unwrap();
+        let cstr = buffer.as_cstr().unwrap();
+        assert_eq!(cstr.to_bytes_with_nul(), b"abc\0");
+        assert_eq!(buffer, "abc");
+        buffer.push_str("d").unwrap();
+        assert_eq!(buffer.as_cstr().unwrap().to_bytes(), b"abcd");
+
+        let mut buffer = WriteBuffer::<8>::try_from("a\0b").unwrap();
+        assert_eq!(
+            buffer.as_cstr().unwrap_err(),
+            CStrError::InteriorNul { index: 1 }
+        );
+
+        let mut buffer = WriteBuffer::<3>::try_from("abc").unwrap();
+        assert_eq!(
+            buffer.as_cstr().unwrap_err(),
+            CStrError::Capacity(CapacityError {
+                requested: 1,
+                available: 0,
+            })
+        );
+        assert_eq!(buffer, "abc");
+    }
+
+    #[test]
+    fn test_invalid_utf8_trait_impls() {
+        use std::collections::BTreeSet;
+        use std::fmt::Write as _;
+
+        let mut buffer: WriteBuffer<8> = WriteBuffer::new();
+        buffer.write_bytes(b"a\xff").unwrap();
+
+        let mut out = String::new();
+        assert_eq!(write!(out, "{}", buffer), Err(std::fmt::Error));
+        assert_eq!(buffer.drain_to(&mut out), Err(std::fmt::Error));
+        assert_eq!(buffer.len(), 2);
+        assert_ne!(buffer, "a");
+
+        let mut other: WriteBuffer<8> = WriteBuffer::new();
+        other.write_bytes(b"a\xfe").unwrap();
+        assert!(other < buffer);
+        let set: BTreeSet<_> = [&buffer, &other].iter().copied().collect();
+        assert_eq!(set.len(), 2);
+        assert_ne!(format!("{:?}", buffer), "");
+
+        #[cfg(feature = "serde")]
+        assert!(serde_json::to_string(&buffer).is_err());
+    }
+
+    #[test]
+    fn test_max_len_seen_after_split_and_rollback() {
+        let mut buffer = WriteBuffer::<16>::try_from("hello world").unwrap();
+        let tail = buffer.split_off(5).unwrap();
+        assert_eq!(tail, " world");
+        assert_eq!(tail.max_len_seen(), 6);
+
+        let mut buffer: WriteBuffer<8> = WriteBuffer::new();
+        buffer.write_all_str(["abcd", "efgh", "ij"]).unwrap_err();
+        assert_eq!(buffer.len(), 0);
+        assert_eq!(buffer.max_len_seen(), 0);
+        buffer.write_display_list([1234, 5678], ", ").unwrap_err();
+        assert_eq!(buffer.max_len_seen(), 0);
+        buffer
+            .write_fmt_checked(format_args!("{}{}", "abcdef", "ghi"))
+            .unwrap_err();
+        assert_eq!(buffer.max_len_seen(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "a formatting trait implementation returned an error")]
+    fn test_write_fmt_checked_failing_display() {
+        struct Failing;
+
+        impl core::fmt::Display for Failing {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                f.write_str("ab")?;
+                Err(core::fmt::Error)
+            }
+        }
+
+        let mut buffer: WriteBuffer<8> = WriteBuffer::new();
+        let _ = buffer.write_fmt_checked(format_args!("{}", Failing));
+    }
+
+    #[test]
+    fn test_wrap_with_failing_display() {
+        struct Failing;
+
+        impl core::fmt::Display for Failing {
+            fn fmt(&self, _: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                Err(core::fmt::Error)
+            }
+        }
+
+        let mut buffer = WriteBuffer::<4>::with_policy(OverflowPolicy::Wrap);
+        buffer.push_str("abc").unwrap();
+        let prefix = "xé";
+        write!(buffer, "{}{}", prefix, Failing).unwrap_err();
+        // The content was shifted, so the partial output is kept
+        assert_eq!(buffer.as_str_checked(), Ok("cxé"));
+        assert_eq!(buffer.try_as_str(), Ok("cxé"));
+
+        buffer.reset();
+        buffer.push_str("ab").unwrap();
+        write!(buffer, "c{}", Failing).unwrap_err();
+        assert_eq!(buffer, "ab");
+    }
+
+    #[test]
+    fn test_truncate_rollback() {
+        struct Failing;
+
+        impl core::fmt::Display for Failing {
+            fn fmt(&self, _: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                Err(core::fmt::Error)
+            }
+        }
+
+        // A failed write doesn't leave the flag behind
+        let mut buffer = WriteBuffer::<4>::with_policy(OverflowPolicy::Truncate);
+        write!(buffer, "ab").unwrap();
+        write!(buffer, "cdef{}", Failing).unwrap_err();
+        assert_eq!(buffer, "ab");
+        assert!(!buffer.truncated());
+        write!(buffer, "cd").unwrap();
+        assert_eq!(buffer, "abcd");
     }
 }
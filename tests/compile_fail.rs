@@ -0,0 +1,11 @@
+// The expected errors depend on the exact compiler version, so they are only
+// checked when `E_WRITE_BUFFER_COMPILE_FAIL` is set, as CI does on the
+// toolchain the `.stderr` files were generated with.
+#[test]
+fn compile_fail() {
+    if std::env::var_os("E_WRITE_BUFFER_COMPILE_FAIL").is_none() {
+        return;
+    }
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
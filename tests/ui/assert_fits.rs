@@ -0,0 +1,7 @@
+use e_write_buffer::WriteBuffer;
+
+const TEMPLATE: &str = "temperature: ";
+
+const _: () = WriteBuffer::<8>::assert_fits::<{ TEMPLATE.len() }>();
+
+fn main() {}